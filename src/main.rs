@@ -5,24 +5,30 @@ mod windows;
 
 use gio::ApplicationFlags;
 use gtk4::prelude::*;
-use gtk4::{glib, Settings};
+use gtk4::{gdk, glib, Settings};
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
+    collections::HashMap,
     rc::{Rc, Weak},
     sync::Arc,
 };
 use tokio::sync::mpsc;
 use utils::{
-    apply_css, load_config,
+    app_index, apply_css, init_compositor, load_config,
     network::{NetworkCommand, NetworkResult, NetworkService, NetworkUtilError},
     notification_manager,
     notification_server::{self, NotificationServer},
     BarConfig,
 };
 use widgets::NetworkWidget;
-use windows::{BarWindow, DockWindow, NetworkWindow};
+use windows::{BarWindow, DockWindow, NetworkWindow, WindowSwitcher};
 
 const APP_ID: &str = "com.github.linuxmobile.kaneru";
+const WINDOW_SWITCHER_ACTION: &str = "window-switcher";
+const WINDOW_SWITCHER_FLAG: &str = "--window-switcher";
+
+type OutputBars = Rc<RefCell<HashMap<String, BarWindow>>>;
+type OutputDocks = Rc<RefCell<HashMap<String, Rc<DockWindow>>>>;
 
 async fn network_actor_task(
     mut command_rx: mpsc::Receiver<NetworkCommand>,
@@ -61,8 +67,7 @@ async fn network_actor_task(
 
 fn setup_network_result_handler(
     rx: mpsc::UnboundedReceiver<NetworkResult>,
-    widget_weak: Weak<NetworkWidget>,
-    window_weak: Weak<NetworkWindow>,
+    bars_weak: Weak<RefCell<HashMap<String, BarWindow>>>,
 ) {
     let rx = Rc::new(RefCell::new(rx));
 
@@ -72,16 +77,30 @@ fn setup_network_result_handler(
         for _ in 0..5 {
             match rx_guard.try_recv() {
                 Ok(result) => {
-                    let result_clone = result;
-                    let widget_weak_clone = widget_weak.clone();
-                    let window_weak_clone = window_weak.clone();
+                    let bars_weak_clone = bars_weak.clone();
 
                     glib::idle_add_local_once(move || {
-                        process_network_result(
-                            result_clone,
-                            &widget_weak_clone,
-                            &window_weak_clone,
-                        );
+                        let Some(bars) = bars_weak_clone.upgrade() else {
+                            return;
+                        };
+                        let targets: Vec<(Weak<NetworkWidget>, Weak<NetworkWindow>)> = bars
+                            .borrow()
+                            .values()
+                            .filter(|bar| {
+                                bar.network_widget.is_some() || bar.network_window.is_some()
+                            })
+                            .map(|bar| {
+                                let widget = bar.network_widget.as_ref();
+                                let window = bar.network_window.as_ref();
+                                (
+                                    widget.map_or(Weak::new(), Rc::downgrade),
+                                    window.map_or(Weak::new(), Rc::downgrade),
+                                )
+                            })
+                            .collect();
+                        for (widget_weak, window_weak) in &targets {
+                            process_network_result(result.clone(), widget_weak, window_weak);
+                        }
                     });
                 }
                 Err(tokio::sync::mpsc::error::TryRecvError::Empty) => {
@@ -157,10 +176,12 @@ fn process_network_result(
 #[tokio::main]
 async fn main() -> glib::ExitCode {
     let config = load_config();
+    init_compositor(config.compositor);
+    app_index::init(config.app_id_map.clone());
 
     let app = gtk4::Application::builder()
         .application_id(APP_ID)
-        .flags(ApplicationFlags::default())
+        .flags(ApplicationFlags::HANDLES_COMMAND_LINE)
         .build();
 
     let (notify_tx, notify_rx) = mpsc::channel(32);
@@ -201,16 +222,18 @@ async fn main() -> glib::ExitCode {
         eprintln!("Failed to initialize NetworkService: {}", e);
     }
 
-    let bar_window_holder: Rc<RefCell<Option<BarWindow>>> = Rc::new(RefCell::new(None));
-    let network_widget_holder: Rc<RefCell<Option<Rc<NetworkWidget>>>> = Rc::new(RefCell::new(None));
-    let network_window_holder: Rc<RefCell<Option<Rc<NetworkWindow>>>> = Rc::new(RefCell::new(None));
-    let dock_holder: Rc<RefCell<Option<Rc<DockWindow>>>> = Rc::new(RefCell::new(None));
-
+    let bars: OutputBars = Rc::new(RefCell::new(HashMap::new()));
+    let docks: OutputDocks = Rc::new(RefCell::new(HashMap::new()));
 
     let config_clone_startup = config.clone();
-    app.connect_startup(move |_| {
+    app.connect_startup(move |app| {
         apply_css();
 
+        let switcher = WindowSwitcher::new(app);
+        let switcher_action = gio::SimpleAction::new(WINDOW_SWITCHER_ACTION, None);
+        switcher_action.connect_activate(move |_, _| switcher.toggle());
+        app.add_action(&switcher_action);
+
         if let Some(font_name) = &config_clone_startup.font {
             if let Some(settings) = Settings::default() {
                 settings.set_property("gtk-font-name", font_name);
@@ -222,49 +245,65 @@ async fn main() -> glib::ExitCode {
 
     let config_clone_activate = config.clone();
     let net_command_tx_clone = net_command_tx.clone();
-    let bar_window_holder_clone = bar_window_holder.clone();
-    let network_widget_holder_clone = network_widget_holder.clone();
-    let network_window_holder_clone = network_window_holder.clone();
-    let dock_holder_clone = dock_holder.clone();
-
+    let bars_clone = bars.clone();
+    let docks_clone = docks.clone();
+    let monitors_watched = Rc::new(Cell::new(false));
 
     let net_result_rx_holder = Rc::new(RefCell::new(Some(net_result_rx)));
 
     app.connect_activate(move |app| {
-        let built_bar = build_ui(
+        sync_outputs(
             app,
             &config_clone_activate,
-            net_command_tx_clone.clone(),
+            &bars_clone,
+            &docks_clone,
+            &net_command_tx_clone,
             network_service_available,
         );
-        built_bar.present();
-
-        *network_widget_holder_clone.borrow_mut() = built_bar.network_widget.clone();
-        *network_window_holder_clone.borrow_mut() = built_bar.network_window.clone();
-        *bar_window_holder_clone.borrow_mut() = Some(built_bar);
-
-        let dock_window = DockWindow::new(app, &config_clone_activate.dock);
-        dock_window.present();
-        *dock_holder_clone.borrow_mut() = Some(dock_window);
 
+        if !monitors_watched.replace(true) {
+            if let Some(display) = gdk::Display::default() {
+                let app_weak = app.downgrade();
+                let config = config_clone_activate.clone();
+                let bars = bars_clone.clone();
+                let docks = docks_clone.clone();
+                let net_command_tx = net_command_tx_clone.clone();
+                display.monitors().connect_items_changed(move |_, _, _, _| {
+                    if let Some(app) = app_weak.upgrade() {
+                        sync_outputs(
+                            &app,
+                            &config,
+                            &bars,
+                            &docks,
+                            &net_command_tx,
+                            network_service_available,
+                        );
+                    }
+                });
+            }
+        }
 
         if network_service_available {
             if let Some(rx) = net_result_rx_holder.borrow_mut().take() {
-                let nw_widget_weak = network_widget_holder_clone
-                    .borrow()
-                    .as_ref()
-                    .map_or(Weak::new(), |rc| Rc::downgrade(rc));
-
-                let nw_window_weak = network_window_holder_clone
-                    .borrow()
-                    .as_ref()
-                    .map_or(Weak::new(), |rc| Rc::downgrade(rc));
-
-                setup_network_result_handler(rx, nw_widget_weak, nw_window_weak);
+                setup_network_result_handler(rx, Rc::downgrade(&bars_clone));
             }
         }
     });
 
+    app.connect_command_line(|app, command_line| {
+        let wants_switcher = command_line
+            .arguments()
+            .iter()
+            .any(|arg| arg.to_str() == Some(WINDOW_SWITCHER_FLAG));
+        if !wants_switcher || !command_line.is_remote() {
+            app.activate();
+        }
+        if wants_switcher {
+            app.activate_action(WINDOW_SWITCHER_ACTION, None);
+        }
+        glib::ExitCode::SUCCESS
+    });
+
     let exit_code = app.run();
 
     server_handle.abort();
@@ -272,11 +311,84 @@ async fn main() -> glib::ExitCode {
     exit_code
 }
 
+fn monitors(display: &gdk::Display) -> Vec<(String, gdk::Monitor)> {
+    let model = display.monitors();
+    (0..model.n_items())
+        .filter_map(|i| model.item(i).and_downcast::<gdk::Monitor>())
+        .filter_map(|monitor| {
+            let connector = monitor.connector()?.to_string();
+            Some((connector, monitor))
+        })
+        .collect()
+}
+
+fn output_enabled(outputs: &[String], connector: &str) -> bool {
+    outputs.is_empty() || outputs.iter().any(|name| name == connector)
+}
+
+fn sync_outputs(
+    app: &gtk4::Application,
+    config: &BarConfig,
+    bars: &OutputBars,
+    docks: &OutputDocks,
+    net_command_tx: &mpsc::Sender<NetworkCommand>,
+    network_service_available: bool,
+) {
+    let Some(display) = gdk::Display::default() else {
+        eprintln!("Error: Could not get default GdkDisplay to enumerate monitors.");
+        return;
+    };
+    let connected = monitors(&display);
+
+    bars.borrow_mut().retain(|connector, bar| {
+        let keep = connected.iter().any(|(name, _)| name == connector);
+        if !keep {
+            bar.close();
+        }
+        keep
+    });
+    docks.borrow_mut().retain(|connector, dock| {
+        let keep = connected.iter().any(|(name, _)| name == connector);
+        if !keep {
+            dock.close();
+        }
+        keep
+    });
+
+    for (connector, monitor) in &connected {
+        let model = monitor.model();
+        let config = config.for_output(connector, model.as_deref());
+
+        let needs_bar = output_enabled(&config.bar_outputs, connector)
+            && !bars.borrow().contains_key(connector);
+        if needs_bar {
+            let bar = build_ui(
+                app,
+                &config,
+                monitor,
+                net_command_tx.clone(),
+                network_service_available,
+            );
+            bar.present();
+            bars.borrow_mut().insert(connector.clone(), bar);
+        }
+
+        let needs_dock = output_enabled(&config.dock.outputs, connector)
+            && !docks.borrow().contains_key(connector);
+        if needs_dock {
+            let dock = DockWindow::new(app, &config.dock, monitor);
+            dock.present();
+            docks.borrow_mut().insert(connector.clone(), dock);
+        }
+    }
+}
+
 fn build_ui(
     app: &gtk4::Application,
     config: &BarConfig,
+    monitor: &gdk::Monitor,
     net_command_tx: mpsc::Sender<NetworkCommand>,
     network_service_available: bool,
 ) -> BarWindow {
-    BarWindow::new(app, config, net_command_tx, network_service_available)
+    BarWindow::new(app, config, monitor, net_command_tx, network_service_available)
 }
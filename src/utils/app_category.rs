@@ -0,0 +1,82 @@
+/// The freedesktop main categories an application can be browsed under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MainCategory {
+    AudioVideo,
+    Development,
+    Education,
+    Game,
+    Graphics,
+    Network,
+    Office,
+    Science,
+    Settings,
+    System,
+    Utility,
+}
+
+impl MainCategory {
+    pub const ALL: [MainCategory; 11] = [
+        MainCategory::AudioVideo,
+        MainCategory::Development,
+        MainCategory::Education,
+        MainCategory::Game,
+        MainCategory::Graphics,
+        MainCategory::Network,
+        MainCategory::Office,
+        MainCategory::Science,
+        MainCategory::Settings,
+        MainCategory::System,
+        MainCategory::Utility,
+    ];
+
+    /// Maps a `Categories=` entry to its main category; `Audio` and `Video`
+    /// fold into `AudioVideo`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "AudioVideo" | "Audio" | "Video" => Some(MainCategory::AudioVideo),
+            "Development" => Some(MainCategory::Development),
+            "Education" => Some(MainCategory::Education),
+            "Game" => Some(MainCategory::Game),
+            "Graphics" => Some(MainCategory::Graphics),
+            "Network" => Some(MainCategory::Network),
+            "Office" => Some(MainCategory::Office),
+            "Science" => Some(MainCategory::Science),
+            "Settings" => Some(MainCategory::Settings),
+            "System" => Some(MainCategory::System),
+            "Utility" => Some(MainCategory::Utility),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MainCategory::AudioVideo => "Multimedia",
+            MainCategory::Development => "Development",
+            MainCategory::Education => "Education",
+            MainCategory::Game => "Games",
+            MainCategory::Graphics => "Graphics",
+            MainCategory::Network => "Internet",
+            MainCategory::Office => "Office",
+            MainCategory::Science => "Science",
+            MainCategory::Settings => "Settings",
+            MainCategory::System => "System",
+            MainCategory::Utility => "Utilities",
+        }
+    }
+
+    pub fn icon_name(self) -> &'static str {
+        match self {
+            MainCategory::AudioVideo => "applications-multimedia-symbolic",
+            MainCategory::Development => "applications-engineering-symbolic",
+            MainCategory::Education => "accessories-dictionary-symbolic",
+            MainCategory::Game => "applications-games-symbolic",
+            MainCategory::Graphics => "applications-graphics-symbolic",
+            MainCategory::Network => "web-browser-symbolic",
+            MainCategory::Office => "x-office-document-symbolic",
+            MainCategory::Science => "applications-science-symbolic",
+            MainCategory::Settings => "preferences-system-symbolic",
+            MainCategory::System => "applications-system-symbolic",
+            MainCategory::Utility => "applications-utilities-symbolic",
+        }
+    }
+}
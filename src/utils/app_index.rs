@@ -0,0 +1,100 @@
+use crate::utils::AppResolver;
+use gtk4::prelude::*;
+use gtk4::{gio, glib};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::{Arc, Once, OnceLock},
+    time::Duration,
+};
+use tokio::sync::watch;
+
+/// Installs and removals touch several files at once; wait for them to settle.
+const RESCAN_DELAY: Duration = Duration::from_millis(500);
+
+static INDEX: OnceLock<watch::Sender<AppIndex>> = OnceLock::new();
+static STARTED: Once = Once::new();
+static APP_ID_MAP: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+thread_local! {
+    static MONITORS: RefCell<Vec<gio::FileMonitor>> = const { RefCell::new(Vec::new()) };
+    static RESCAN_TIMER: RefCell<Option<glib::SourceId>> = const { RefCell::new(None) };
+}
+
+/// The shared application index; `None` until the first scan has finished.
+pub type AppIndex = Option<Arc<AppResolver>>;
+
+fn sender() -> &'static watch::Sender<AppIndex> {
+    INDEX.get_or_init(|| watch::channel(None).0)
+}
+
+fn rescan() {
+    glib::MainContext::default().spawn_local(async move {
+        let app_id_map = APP_ID_MAP.get().cloned().unwrap_or_default();
+        let scan = move || AppResolver::with_app_id_map(&app_id_map);
+        match tokio::task::spawn_blocking(scan).await {
+            Ok(resolver) => {
+                sender().send_replace(Some(Arc::new(resolver)));
+            }
+            Err(e) => eprintln!("Failed to scan applications: {}", e),
+        }
+    });
+}
+
+fn schedule_rescan() {
+    RESCAN_TIMER.with(|timer| {
+        if let Some(source) = timer.borrow_mut().take() {
+            source.remove();
+        }
+        let source = glib::timeout_add_local_once(RESCAN_DELAY, || {
+            RESCAN_TIMER.with(|timer| timer.borrow_mut().take());
+            rescan();
+        });
+        *timer.borrow_mut() = Some(source);
+    });
+}
+
+fn is_desktop_file(file: &gio::File) -> bool {
+    file.basename()
+        .is_some_and(|name| name.extension().is_some_and(|ext| ext == "desktop"))
+}
+
+fn watch_directories() {
+    for dir in AppResolver::desktop_file_paths() {
+        let file = gio::File::for_path(&dir);
+        match file.monitor_directory(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE) {
+            Ok(monitor) => {
+                monitor.connect_changed(|_, file, other_file, event| {
+                    // Renames report the new name in `other_file`.
+                    let touches_desktop_file = [Some(file), other_file]
+                        .into_iter()
+                        .flatten()
+                        .any(is_desktop_file);
+                    if touches_desktop_file && event != gio::FileMonitorEvent::AttributeChanged {
+                        schedule_rescan();
+                    }
+                });
+                MONITORS.with(|monitors| monitors.borrow_mut().push(monitor));
+            }
+            Err(e) => eprintln!("Failed to watch {}: {}", dir.display(), e),
+        }
+    }
+}
+
+/// Sets the configured app id to desktop id overrides. Must be called before
+/// the first `subscribe`.
+pub fn init(app_id_map: HashMap<String, String>) {
+    if APP_ID_MAP.set(app_id_map).is_err() {
+        eprintln!("Application index already initialized, ignoring app-id-map");
+    }
+}
+
+/// Subscribes to the application index, scanning and watching the applications
+/// directories on first use. Must be called from the GTK main thread.
+pub fn subscribe() -> watch::Receiver<AppIndex> {
+    STARTED.call_once(|| {
+        watch_directories();
+        rescan();
+    });
+    sender().subscribe()
+}
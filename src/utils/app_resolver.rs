@@ -1,15 +1,53 @@
+use crate::utils::{app_category::MainCategory, fuzzy::fuzzy_score, launcher};
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
+#[derive(Debug, Clone)]
+pub struct DesktopAction {
+    pub id: String,
+    pub name: String,
+    pub exec: String,
+    pub icon: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct AppInfo {
     pub desktop_id: String,
+    pub path: PathBuf,
     pub name: String,
     pub icon: String,
     pub exec: String,
     pub generic_name: Option<String>,
+    /// Untranslated Name/GenericName values that differ from the localized ones.
+    pub alt_names: Vec<String>,
     pub keywords: Vec<String>,
+    pub categories: Vec<String>,
+    pub actions: Vec<DesktopAction>,
+    pub startup_wm_class: Option<String>,
+    /// Lowercase compositor app ids this entry's windows are expected to use.
+    pub app_ids: Vec<String>,
+    pub terminal: bool,
+    pub working_dir: Option<PathBuf>,
+    pub dbus_activatable: bool,
+}
+
+impl AppInfo {
+    pub fn main_categories(&self) -> impl Iterator<Item = MainCategory> + '_ {
+        let mut seen = Vec::new();
+        self.categories
+            .iter()
+            .filter_map(|name| MainCategory::from_name(name))
+            .filter(move |category| {
+                let first = !seen.contains(category);
+                seen.push(*category);
+                first
+            })
+    }
+
+    pub fn in_category(&self, category: MainCategory) -> bool {
+        self.main_categories().any(|c| c == category)
+    }
 }
 
 impl AppResolver {
@@ -17,36 +55,192 @@ impl AppResolver {
         self.apps_by_desktop_id.get(&desktop_id.to_lowercase())
     }
 
-    pub fn clean_exec(exec: &str) -> Vec<String> {
-        exec.split_whitespace()
-            .filter(|part| {
-                !part.starts_with('%')
-            })
-            .map(|s| s.to_string())
-            .collect()
+    pub fn apps(&self) -> impl Iterator<Item = &AppInfo> {
+        self.apps_by_desktop_id.values()
     }
+
+    /// Finds the entry a running window belongs to: configured overrides first,
+    /// then desktop id, `StartupWMClass` and executable name. An override whose
+    /// desktop file is missing falls through to the other lookups.
+    pub fn resolve_by_app_id(&self, app_id: &str) -> Option<&AppInfo> {
+        let app_id = app_id.to_lowercase();
+        let mapped = self
+            .app_id_map
+            .get(&app_id)
+            .and_then(|desktop_id| self.resolve_by_desktop_id(desktop_id));
+        if mapped.is_some() {
+            return mapped;
+        }
+        let app_id = app_id.strip_suffix(".desktop").unwrap_or(&app_id);
+
+        self.apps_by_desktop_id
+            .get(app_id)
+            .or_else(|| self.apps_by_wm_class.get(app_id))
+            .or_else(|| self.apps_by_exec.get(app_id))
+    }
+}
+
+fn normalize_desktop_id(desktop_id: &str) -> String {
+    let desktop_id = desktop_id.to_lowercase();
+    match desktop_id.strip_suffix(".desktop") {
+        Some(stripped) => stripped.to_string(),
+        None => desktop_id,
+    }
+}
+
+/// Resolves the string-level escapes (`\s`, `\n`, `\t`, `\r`, `\\`) of a desktop entry value.
+fn unescape_value(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some('\\') => unescaped.push('\\'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Locale keys to try for `locale`, most specific first, following the
+/// `lang_COUNTRY@MODIFIER` matching rules of the desktop entry spec.
+fn locale_candidates(locale: &str) -> Vec<String> {
+    let (base, modifier) = match locale.split_once('@') {
+        Some((base, modifier)) => (base, Some(modifier)),
+        None => (locale, None),
+    };
+    let base = base.split('.').next().unwrap_or(base);
+    let (lang, country) = match base.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (base, None),
+    };
+    if lang.is_empty() || lang == "C" || lang == "POSIX" {
+        return Vec::new();
+    }
+
+    let mut candidates = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        candidates.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        candidates.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        candidates.push(format!("{}@{}", lang, modifier));
+    }
+    candidates.push(lang.to_string());
+    candidates
+}
+
+fn current_locales() -> Vec<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|value| !value.is_empty()))
+        .map(|locale| locale_candidates(&locale))
+        .unwrap_or_default()
+}
+
+/// Splits `Key=value` or `Key[locale]=value` for the given key.
+fn split_localized_key<'a>(line: &'a str, key: &str) -> Option<(Option<&'a str>, &'a str)> {
+    let rest = line.strip_prefix(key)?;
+    if let Some(value) = rest.strip_prefix('=') {
+        return Some((None, value));
+    }
+    let (locale, rest) = rest.strip_prefix('[')?.split_once(']')?;
+    Some((Some(locale), rest.strip_prefix('=')?))
+}
+
+#[derive(Default)]
+struct LocalizedValue {
+    untranslated: Option<String>,
+    translations: HashMap<String, String>,
+}
+
+impl LocalizedValue {
+    fn set(&mut self, locale: Option<&str>, value: String) {
+        match locale {
+            Some(locale) => {
+                self.translations.insert(locale.to_string(), value);
+            }
+            None => self.untranslated = Some(value),
+        }
+    }
+
+    fn resolve(&self, locales: &[String]) -> Option<String> {
+        locales
+            .iter()
+            .find_map(|locale| self.translations.get(locale))
+            .or(self.untranslated.as_ref())
+            .cloned()
+    }
+}
+
+fn split_keywords(value: &str) -> Vec<String> {
+    value
+        .split(';')
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn desktop_matches(list: &str, desktops: &[String]) -> bool {
+    list.split(';')
+        .map(str::trim)
+        .any(|desktop| desktops.iter().any(|d| d.eq_ignore_ascii_case(desktop)))
 }
 
 pub struct AppResolver {
     apps_by_name: HashMap<String, AppInfo>,
     apps_by_exec: HashMap<String, AppInfo>,
     apps_by_desktop_id: HashMap<String, AppInfo>,
+    apps_by_wm_class: HashMap<String, AppInfo>,
+    /// Lowercase app id to desktop id overrides from the config.
+    app_id_map: HashMap<String, String>,
+    desktops: Vec<String>,
+    locales: Vec<String>,
 }
 
 impl AppResolver {
     pub fn new() -> Self {
-        let mut resolver = Self {
+        Self::with_app_id_map(&HashMap::new())
+    }
+    
+    pub fn with_app_id_map(app_id_map: &HashMap<String, String>) -> Self {
+        let mut resolver = Self::empty(app_id_map);
+        resolver.scan_all_applications();
+        resolver
+    }
+    
+    fn empty(app_id_map: &HashMap<String, String>) -> Self {
+        Self {
             apps_by_name: HashMap::new(),
             apps_by_exec: HashMap::new(),
             apps_by_desktop_id: HashMap::new(),
-        };
-        
-        resolver.scan_all_applications();
-        resolver
+            apps_by_wm_class: HashMap::new(),
+            app_id_map: app_id_map
+                .iter()
+                .map(|(app_id, desktop_id)| (app_id.to_lowercase(), normalize_desktop_id(desktop_id)))
+                .collect(),
+            desktops: std::env::var("XDG_CURRENT_DESKTOP")
+                .map(|value| value.split(':').map(String::from).collect())
+                .unwrap_or_default(),
+            locales: current_locales(),
+        }
     }
     
     fn scan_all_applications(&mut self) {
-        let search_paths = self.get_desktop_file_paths();
+        let search_paths = Self::desktop_file_paths();
         
         for path in search_paths {
             if path.exists() {
@@ -55,7 +249,7 @@ impl AppResolver {
         }
     }
     
-    fn get_desktop_file_paths(&self) -> Vec<PathBuf> {
+    pub fn desktop_file_paths() -> Vec<PathBuf> {
         let mut paths = vec![
             PathBuf::from("/usr/share/applications"),
             PathBuf::from("/usr/local/share/applications"),
@@ -94,13 +288,24 @@ impl AppResolver {
         let content = fs::read_to_string(path).ok()?;
         let desktop_id = path.file_stem()?.to_str()?.to_string();
         
-        let mut name = None;
+        let mut name = LocalizedValue::default();
         let mut icon = None;
         let mut exec = None;
-        let mut generic_name = None;
-        let mut keywords = Vec::new();
+        let mut generic_name = LocalizedValue::default();
+        let mut keywords = LocalizedValue::default();
         let mut no_display = false;
         let mut app_type = None;
+        let mut terminal = false;
+        let mut working_dir = None;
+        let mut dbus_activatable = false;
+        let mut startup_wm_class = None;
+        let mut categories = Vec::new();
+        let mut only_show_in = None;
+        let mut not_show_in = None;
+        let mut action_ids: Vec<String> = Vec::new();
+        let mut actions: HashMap<String, DesktopAction> = HashMap::new();
+        let mut action_names: HashMap<String, LocalizedValue> = HashMap::new();
+        let mut current_action: Option<String> = None;
         let mut in_desktop_entry = false;
         
         for line in content.lines() {
@@ -108,9 +313,35 @@ impl AppResolver {
             
             if line == "[Desktop Entry]" {
                 in_desktop_entry = true;
+                current_action = None;
+                continue;
+            } else if line.starts_with("[Desktop Action ") && line.ends_with(']') {
+                in_desktop_entry = false;
+                current_action = Some(line[16..line.len() - 1].to_string());
                 continue;
             } else if line.starts_with('[') && line.ends_with(']') {
                 in_desktop_entry = false;
+                current_action = None;
+                continue;
+            }
+            
+            if let Some(action_id) = &current_action {
+                let action = actions.entry(action_id.clone()).or_insert_with(|| DesktopAction {
+                    id: action_id.clone(),
+                    name: String::new(),
+                    exec: String::new(),
+                    icon: None,
+                });
+                if let Some((locale, value)) = split_localized_key(line, "Name") {
+                    action_names
+                        .entry(action_id.clone())
+                        .or_default()
+                        .set(locale, unescape_value(value));
+                } else if line.starts_with("Exec=") {
+                    action.exec = unescape_value(&line[5..]);
+                } else if line.starts_with("Icon=") && line.len() > 5 {
+                    action.icon = Some(line[5..].to_string());
+                }
                 continue;
             }
             
@@ -118,29 +349,37 @@ impl AppResolver {
                 continue;
             }
             
-            if line.starts_with("Name=") && !line.contains('[') {
-                name = Some(line[5..].to_string());
+            if let Some((locale, value)) = split_localized_key(line, "Name") {
+                name.set(locale, unescape_value(value));
+            } else if let Some((locale, value)) = split_localized_key(line, "GenericName") {
+                generic_name.set(locale, unescape_value(value));
+            } else if let Some((locale, value)) = split_localized_key(line, "Keywords") {
+                keywords.set(locale, value.to_string());
             } else if line.starts_with("Icon=") {
                 let icon_value = line[5..].to_string();
                 if !icon_value.is_empty() {
                     icon = Some(icon_value);
                 }
             } else if line.starts_with("Exec=") {
-                let exec_value = line[5..].to_string();
-                let clean_exec = exec_value
-                    .replace("%u", "")
-                    .replace("%U", "")
-                    .replace("%f", "")
-                    .replace("%F", "")
-                    .trim()
-                    .to_string();
-                exec = Some(clean_exec);
-            } else if line.starts_with("GenericName=") && !line.contains('[') {
-                generic_name = Some(line[12..].to_string());
-            } else if line.starts_with("Keywords=") {
-                keywords = line[9..].split(';').map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty()).collect();
-            } else if line == "NoDisplay=true" {
+                exec = Some(unescape_value(&line[5..]));
+            } else if line.starts_with("Actions=") {
+                action_ids = line[8..].split(';').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect();
+            } else if line == "NoDisplay=true" || line == "Hidden=true" {
                 no_display = true;
+            } else if line == "Terminal=true" {
+                terminal = true;
+            } else if line == "DBusActivatable=true" {
+                dbus_activatable = true;
+            } else if line.starts_with("StartupWMClass=") && line.len() > 15 {
+                startup_wm_class = Some(line[15..].to_string());
+            } else if line.starts_with("Path=") && line.len() > 5 {
+                working_dir = Some(PathBuf::from(unescape_value(&line[5..])));
+            } else if line.starts_with("Categories=") {
+                categories = line[11..].split(';').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect();
+            } else if line.starts_with("OnlyShowIn=") {
+                only_show_in = Some(line[11..].to_string());
+            } else if line.starts_with("NotShowIn=") {
+                not_show_in = Some(line[10..].to_string());
             } else if line.starts_with("Type=") {
                 app_type = Some(line[5..].to_string());
             }
@@ -149,18 +388,83 @@ impl AppResolver {
         if no_display || app_type.as_deref() != Some("Application") {
             return None;
         }
+        if only_show_in.is_some_and(|list| !desktop_matches(&list, &self.desktops))
+            || not_show_in.is_some_and(|list| desktop_matches(&list, &self.desktops))
+        {
+            return None;
+        }
         
-        let name = name?;
+        let localized_name = name.resolve(&self.locales)?;
+        let localized_generic_name = generic_name.resolve(&self.locales);
+        let alt_names = [&name.untranslated, &generic_name.untranslated]
+            .into_iter()
+            .flatten()
+            .filter(|alt| {
+                **alt != localized_name && Some(*alt) != localized_generic_name.as_ref()
+            })
+            .cloned()
+            .collect();
+
+        let mut all_keywords = keywords
+            .resolve(&self.locales)
+            .map(|value| split_keywords(&value))
+            .unwrap_or_default();
+        if let Some(untranslated) = &keywords.untranslated {
+            for keyword in split_keywords(untranslated) {
+                if !all_keywords.contains(&keyword) {
+                    all_keywords.push(keyword);
+                }
+            }
+        }
+
         let icon = icon.unwrap_or_else(|| "application-x-executable".to_string());
         let exec = exec?;
+        let actions = action_ids
+            .iter()
+            .filter_map(|id| {
+                let mut action = actions.remove(id)?;
+                if let Some(name) = action_names.get(id).and_then(|n| n.resolve(&self.locales)) {
+                    action.name = name;
+                }
+                Some(action)
+            })
+            .filter(|action| !action.name.is_empty() && !action.exec.is_empty())
+            .collect();
+        
+        let desktop_key = desktop_id.to_lowercase();
+        let mut app_ids = vec![desktop_key.clone()];
+        let candidates = startup_wm_class
+            .iter()
+            .map(|wm_class| wm_class.to_lowercase())
+            .chain([self.extract_command_name(&exec).to_lowercase()])
+            .chain(
+                self.app_id_map
+                    .iter()
+                    .filter(|(_, target)| **target == desktop_key)
+                    .map(|(app_id, _)| app_id.clone()),
+            );
+        for app_id in candidates {
+            if !app_id.is_empty() && !app_ids.contains(&app_id) {
+                app_ids.push(app_id);
+            }
+        }
         
         Some(AppInfo {
             desktop_id,
-            name,
+            path: path.clone(),
+            name: localized_name,
             icon,
             exec,
-            generic_name,
-            keywords,
+            generic_name: localized_generic_name,
+            alt_names,
+            keywords: all_keywords,
+            categories,
+            actions,
+            startup_wm_class,
+            app_ids,
+            terminal,
+            working_dir,
+            dbus_activatable,
         })
     }
     
@@ -173,10 +477,14 @@ impl AppResolver {
         self.apps_by_exec.insert(exec_key, app_info.clone());
         self.apps_by_desktop_id.insert(desktop_key, app_info.clone());
         
-        if let Some(generic) = &app_info.generic_name {
-            let generic_key = generic.to_lowercase();
-            if !self.apps_by_name.contains_key(&generic_key) {
-                self.apps_by_name.insert(generic_key, app_info.clone());
+        if let Some(wm_class) = &app_info.startup_wm_class {
+            self.apps_by_wm_class.insert(wm_class.to_lowercase(), app_info.clone());
+        }
+        
+        for alias in app_info.generic_name.iter().chain(&app_info.alt_names) {
+            let alias_key = alias.to_lowercase();
+            if !self.apps_by_name.contains_key(&alias_key) {
+                self.apps_by_name.insert(alias_key, app_info.clone());
             }
         }
         
@@ -188,21 +496,15 @@ impl AppResolver {
     }
     
     pub fn extract_command_name(&self, exec: &str) -> String {
-        let clean_exec = exec
-            .replace("%u", "")
-            .replace("%U", "")
-            .replace("%f", "")
-            .replace("%F", "")
-            .replace("--new-window", "")
-            .replace("--incognito", "")
-            .replace("--private-window", "");
-            
-        let trimmed = clean_exec.trim();
-        let command = trimmed
-            .split_whitespace()
-            .next()
-            .unwrap_or(trimmed);
-            
+        let argv = launcher::split_exec(exec)
+            .unwrap_or_else(|_| exec.split_whitespace().map(String::from).collect());
+        // Skip `env VAR=value` wrappers to reach the actual program.
+        let command = argv
+            .iter()
+            .find(|arg| arg.as_str() != "env" && !arg.contains('='))
+            .map(String::as_str)
+            .unwrap_or_default();
+
         command
             .split('/')
             .last()
@@ -239,8 +541,8 @@ impl AppResolver {
             score = score.max(self.calculate_match_score(query, &app.desktop_id.to_lowercase(), &app.desktop_id));
             score = score.max(self.calculate_match_score(query, &self.extract_command_name(&app.exec).to_lowercase(), &app.exec));
             
-            if let Some(generic) = &app.generic_name {
-                score = score.max(self.calculate_match_score(query, &generic.to_lowercase(), generic));
+            for name in app.generic_name.iter().chain(&app.alt_names) {
+                score = score.max(self.calculate_match_score(query, &name.to_lowercase(), name));
             }
             
             for keyword in &app.keywords {
@@ -260,6 +562,33 @@ impl AppResolver {
         }
     }
     
+    /// Ranks `app` against a search query across its names, keywords,
+    /// categories, command and desktop id; `None` when nothing matches.
+    pub fn match_score(&self, query: &str, app: &AppInfo) -> Option<i32> {
+        let query = query.trim().to_lowercase();
+        let command = self.extract_command_name(&app.exec);
+        let fields = std::iter::once((app.name.as_str(), 10))
+            .chain(app.generic_name.iter().chain(&app.alt_names).map(|name| (name.as_str(), 8)))
+            .chain(app.keywords.iter().map(|keyword| (keyword.as_str(), 7)))
+            .chain(app.main_categories().map(|category| (category.label(), 5)))
+            .chain(app.categories.iter().map(|category| (category.as_str(), 5)))
+            .chain([(command.as_str(), 6), (app.desktop_id.as_str(), 5)]);
+        
+        fields
+            .filter_map(|(field, weight)| {
+                let score = fuzzy_score(&query, field)?;
+                // Whole-word and prefix hits should beat scattered subsequences.
+                let bonus = match self.calculate_match_score(&query, &field.to_lowercase(), field) {
+                    100 => 60,
+                    90 => 40,
+                    70 => 20,
+                    _ => 0,
+                };
+                Some((score + bonus) * weight / 10)
+            })
+            .max()
+    }
+    
     fn calculate_match_score(&self, query: &str, key: &str, display_name: &str) -> i32 {
         if key == query {
             return 100;
@@ -330,4 +659,139 @@ impl Default for AppResolver {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolver_from(
+        test: &str,
+        entries: &[(&str, &str)],
+        app_id_map: &HashMap<String, String>,
+    ) -> AppResolver {
+        let dir = std::env::temp_dir().join(format!("kaneru-{}-{}", test, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (desktop_id, content) in entries {
+            fs::write(dir.join(format!("{}.desktop", desktop_id)), content).unwrap();
+        }
+        let mut resolver = AppResolver::empty(app_id_map);
+        resolver.scan_directory(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        resolver
+    }
+
+    #[test]
+    fn locale_candidates_follow_spec_order() {
+        assert_eq!(
+            locale_candidates("sr_YU.UTF-8@Latn"),
+            vec!["sr_YU@Latn", "sr_YU", "sr@Latn", "sr"]
+        );
+        assert_eq!(locale_candidates("de_DE.UTF-8"), vec!["de_DE", "de"]);
+        assert!(locale_candidates("C.UTF-8").is_empty());
+    }
+
+    #[test]
+    fn localized_keys_are_split() {
+        assert_eq!(split_localized_key("Name=Files", "Name"), Some((None, "Files")));
+        assert_eq!(
+            split_localized_key("Name[de]=Dateien", "Name"),
+            Some((Some("de"), "Dateien"))
+        );
+        assert_eq!(split_localized_key("NameX=Files", "Name"), None);
+    }
+
+    #[test]
+    fn resolves_running_app_ids() {
+        let app_id_map = HashMap::from([("IDEA".to_string(), "jetbrains-idea.desktop".to_string())]);
+        let resolver = resolver_from(
+            "app-ids",
+            &[(
+                "jetbrains-idea",
+                "[Desktop Entry]\nType=Application\nName=IntelliJ IDEA\n\
+                 Exec=/opt/idea/bin/idea.sh %f\nStartupWMClass=jetbrains-idea-ce\n",
+            )],
+            &app_id_map,
+        );
+
+        for app_id in ["jetbrains-idea-ce", "idea.sh", "idea", "jetbrains-idea"] {
+            let app = resolver.resolve_by_app_id(app_id);
+            assert_eq!(app.map(|app| app.name.as_str()), Some("IntelliJ IDEA"), "{}", app_id);
+        }
+        assert!(resolver.resolve_by_app_id("idea-ultimate").is_none());
+
+        let app = resolver.resolve_by_desktop_id("jetbrains-idea").unwrap();
+        assert_eq!(app.app_ids, ["jetbrains-idea", "jetbrains-idea-ce", "idea.sh", "idea"]);
+    }
+
+    #[test]
+    fn missing_mapped_desktop_files_fall_through() {
+        let app_id_map = HashMap::from([("foot".to_string(), "fooot.desktop".to_string())]);
+        let resolver = resolver_from(
+            "missing-map",
+            &[("foot", "[Desktop Entry]\nType=Application\nName=Foot\nExec=foot\n")],
+            &app_id_map,
+        );
+
+        let app = resolver.resolve_by_app_id("foot");
+        assert_eq!(app.map(|app| app.name.as_str()), Some("Foot"));
+    }
+
+    #[test]
+    fn match_score_ranks_names_above_keywords() {
+        let resolver = resolver_from(
+            "match-score",
+            &[
+                (
+                    "org.gnome.Nautilus",
+                    "[Desktop Entry]\nType=Application\nName=Files\n\
+                     Keywords=folder;manager;explore;\nExec=nautilus --new-window\n\
+                     Categories=GNOME;GTK;Utility;Core;FileManager;\n",
+                ),
+                (
+                    "firefox",
+                    "[Desktop Entry]\nType=Application\nName=Firefox\n\
+                     GenericName=Web Browser\nExec=firefox %u\n\
+                     Categories=Network;WebBrowser;Audio;Video;\n",
+                ),
+            ],
+            &HashMap::new(),
+        );
+        let files = resolver.resolve_by_desktop_id("org.gnome.Nautilus").unwrap();
+        let firefox = resolver.resolve_by_desktop_id("firefox").unwrap();
+
+        let by_name = resolver.match_score("fi", files).unwrap();
+        assert!(resolver.match_score("fire", firefox).unwrap() > by_name);
+        assert!(resolver.match_score("fire", files).is_none());
+        assert!(resolver.match_score("naut", files).is_some());
+        assert!(resolver.match_score("browser", firefox).is_some());
+        assert!(resolver.match_score("folder", files).unwrap() < resolver.match_score("files", files).unwrap());
+        assert!(resolver.match_score("utilities", files).is_some());
+    }
+
+    #[test]
+    fn main_categories_are_deduplicated() {
+        let resolver = resolver_from(
+            "categories",
+            &[(
+                "mpv",
+                "[Desktop Entry]\nType=Application\nName=mpv\nExec=mpv %U\n\
+                 Categories=AudioVideo;Audio;Video;Player;TV;\n",
+            )],
+            &HashMap::new(),
+        );
+        let mpv = resolver.resolve_by_desktop_id("mpv").unwrap();
+        assert_eq!(mpv.main_categories().collect::<Vec<_>>(), [MainCategory::AudioVideo]);
+        assert!(!mpv.in_category(MainCategory::Network));
+    }
+
+    #[test]
+    fn localized_value_falls_back() {
+        let mut name = LocalizedValue::default();
+        name.set(None, "Files".to_string());
+        name.set(Some("de"), "Dateien".to_string());
+
+        let locales = locale_candidates("de_AT.UTF-8");
+        assert_eq!(name.resolve(&locales).as_deref(), Some("Dateien"));
+        assert_eq!(name.resolve(&locale_candidates("fr_FR")).as_deref(), Some("Files"));
+    }
+}
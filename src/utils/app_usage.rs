@@ -0,0 +1,123 @@
+use crate::utils::persistence::{load_app_usage, save_app_usage};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::watch;
+
+const DAY: u64 = 24 * 60 * 60;
+
+static USAGE: OnceLock<watch::Sender<AppUsage>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageEntry {
+    pub count: u32,
+    /// Unix timestamp in seconds.
+    pub last_used: u64,
+}
+
+/// Launch counts per lowercase desktop id.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AppUsage {
+    entries: HashMap<String, UsageEntry>,
+}
+
+impl AppUsage {
+    fn record_at(&mut self, desktop_id: &str, now: u64) {
+        let entry = self.entries.entry(desktop_id.to_lowercase()).or_default();
+        entry.count = entry.count.saturating_add(1);
+        entry.last_used = now;
+    }
+
+    fn score_at(&self, desktop_id: &str, now: u64) -> f64 {
+        let Some(entry) = self.entries.get(&desktop_id.to_lowercase()) else {
+            return 0.0;
+        };
+        let age = now.saturating_sub(entry.last_used);
+        let recency = match age {
+            age if age < DAY => 4.0,
+            age if age < 7 * DAY => 2.0,
+            age if age < 30 * DAY => 1.0,
+            _ => 0.5,
+        };
+        entry.count as f64 * recency
+    }
+
+    /// Launch count weighted by how recently the app was last used.
+    pub fn score(&self, desktop_id: &str) -> f64 {
+        self.score_at(desktop_id, now())
+    }
+
+    /// Desktop ids ordered by descending score.
+    pub fn most_used(&self) -> Vec<&str> {
+        let now = now();
+        let mut ids: Vec<(&str, f64)> = self
+            .entries
+            .keys()
+            .map(|id| (id.as_str(), self.score_at(id, now)))
+            .collect();
+        ids.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        ids.into_iter().map(|(id, _)| id).collect()
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+fn sender() -> &'static watch::Sender<AppUsage> {
+    USAGE.get_or_init(|| {
+        let usage = load_app_usage().unwrap_or_else(|e| {
+            eprintln!("Failed to load app usage: {}", e);
+            AppUsage::default()
+        });
+        watch::channel(usage).0
+    })
+}
+
+pub fn subscribe() -> watch::Receiver<AppUsage> {
+    sender().subscribe()
+}
+
+/// Counts a launch of `desktop_id` and persists the store.
+pub fn record_launch(desktop_id: &str) {
+    sender().send_modify(|usage| usage.record_at(desktop_id, now()));
+    if let Err(e) = save_app_usage(&sender().borrow()) {
+        eprintln!("Failed to save app usage: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_launches_outweigh_old_ones() {
+        let now = 100 * DAY;
+        let mut usage = AppUsage::default();
+        for _ in 0..3 {
+            usage.record_at("org.gnome.Nautilus", now - 60 * DAY);
+        }
+        usage.record_at("firefox", now - 60);
+
+        assert_eq!(usage.score_at("org.gnome.nautilus", now), 1.5);
+        assert_eq!(usage.score_at("Firefox", now), 4.0);
+        assert_eq!(usage.score_at("kitty", now), 0.0);
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let mut usage = AppUsage::default();
+        usage.record_at("firefox", 42);
+
+        let json = serde_json::to_string(&usage).unwrap();
+        assert_eq!(json, r#"{"firefox":{"count":1,"last_used":42}}"#);
+        assert_eq!(serde_json::from_str::<AppUsage>(&json).unwrap(), usage);
+    }
+}
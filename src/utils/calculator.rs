@@ -0,0 +1,171 @@
+use std::{error::Error, fmt, iter::Peekable, str::Chars};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalcError {
+    UnexpectedChar(char),
+    UnexpectedEnd,
+    InvalidNumber(String),
+    NotFinite,
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalcError::UnexpectedChar(c) => write!(f, "unexpected character '{}'", c),
+            CalcError::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            CalcError::InvalidNumber(s) => write!(f, "invalid number '{}'", s),
+            CalcError::NotFinite => write!(f, "result is not a finite number"),
+        }
+    }
+}
+
+impl Error for CalcError {}
+
+/// Recursive descent over `+ - * / % ^`, parentheses and unary signs.
+/// `^` binds tightest and is right-associative.
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+        self.chars.peek().copied()
+    }
+
+    fn expression(&mut self) -> Result<f64, CalcError> {
+        let mut value = self.term()?;
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.chars.next();
+            let rhs = self.term()?;
+            value = if op == '+' { value + rhs } else { value - rhs };
+        }
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<f64, CalcError> {
+        let mut value = self.unary()?;
+        while let Some(op @ ('*' | '×' | '/' | '÷' | '%')) = self.peek() {
+            self.chars.next();
+            let rhs = self.unary()?;
+            value = match op {
+                '*' | '×' => value * rhs,
+                '/' | '÷' => value / rhs,
+                _ => value % rhs,
+            };
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> Result<f64, CalcError> {
+        match self.peek() {
+            Some('-') => {
+                self.chars.next();
+                Ok(-self.unary()?)
+            }
+            Some('+') => {
+                self.chars.next();
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Result<f64, CalcError> {
+        let base = self.atom()?;
+        if self.peek() == Some('^') {
+            self.chars.next();
+            let exponent = self.unary()?;
+            return Ok(base.powf(exponent));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<f64, CalcError> {
+        match self.peek() {
+            Some('(') => {
+                self.chars.next();
+                let value = self.expression()?;
+                match self.peek() {
+                    Some(')') => {
+                        self.chars.next();
+                        Ok(value)
+                    }
+                    Some(c) => Err(CalcError::UnexpectedChar(c)),
+                    None => Err(CalcError::UnexpectedEnd),
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let mut number = String::new();
+                while let Some(c) = self.chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+                    number.push(c);
+                }
+                number.parse().map_err(|_| CalcError::InvalidNumber(number))
+            }
+            Some(c) => Err(CalcError::UnexpectedChar(c)),
+            None => Err(CalcError::UnexpectedEnd),
+        }
+    }
+}
+
+pub fn evaluate(expression: &str) -> Result<f64, CalcError> {
+    let mut parser = Parser {
+        chars: expression.chars().peekable(),
+    };
+    let value = parser.expression()?;
+    if let Some(c) = parser.peek() {
+        return Err(CalcError::UnexpectedChar(c));
+    }
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(CalcError::NotFinite)
+    }
+}
+
+/// Formats a result without float noise: integers lose the fraction and
+/// everything else is rounded to ten decimals.
+pub fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        return format!("{}", value as i64);
+    }
+    let formatted = format!("{:.10}", value);
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn respects_precedence_and_parentheses() {
+        assert_eq!(evaluate("2 + 3 * 4"), Ok(14.0));
+        assert_eq!(evaluate("(2 + 3) * 4"), Ok(20.0));
+        assert_eq!(evaluate("2 ^ 3 ^ 2"), Ok(512.0));
+        assert_eq!(evaluate("-2 ^ 2"), Ok(-4.0));
+        assert_eq!(evaluate("10 % 4 - -1"), Ok(3.0));
+        assert_eq!(evaluate("7 ÷ 2 × 3"), Ok(10.5));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert_eq!(evaluate("2 +"), Err(CalcError::UnexpectedEnd));
+        assert_eq!(evaluate("(1 + 2"), Err(CalcError::UnexpectedEnd));
+        assert_eq!(evaluate("2 x 3"), Err(CalcError::UnexpectedChar('x')));
+        assert_eq!(
+            evaluate("1.2.3"),
+            Err(CalcError::InvalidNumber("1.2.3".into()))
+        );
+        assert_eq!(evaluate("1 / 0"), Err(CalcError::NotFinite));
+    }
+
+    #[test]
+    fn formats_without_float_noise() {
+        assert_eq!(format_number(0.1 + 0.2), "0.3");
+        assert_eq!(format_number(42.0), "42");
+        assert_eq!(format_number(-2.5), "-2.5");
+    }
+}
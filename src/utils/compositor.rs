@@ -0,0 +1,296 @@
+use crate::utils::{hyprland::HyprlandCompositor, niri::NiriCompositor, sway::SwayCompositor};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    env,
+    error::Error,
+    fmt,
+    net::Shutdown,
+    os::unix::net::UnixStream,
+    sync::{mpsc, Once, OnceLock},
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::sync::watch;
+
+const RECONNECT_DELAY_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_DELAY_MAX: Duration = Duration::from_secs(30);
+/// Events arriving this soon after the first one of a burst are handled together.
+const EVENT_COALESCE: Duration = Duration::from_millis(30);
+
+static COMPOSITOR: OnceLock<Box<dyn Compositor>> = OnceLock::new();
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompositorKind {
+    #[default]
+    Auto,
+    Niri,
+    Hyprland,
+    Sway,
+}
+
+#[derive(Debug)]
+pub enum CompositorError {
+    NotRunning(&'static str),
+    Ipc(String),
+    InvalidReply(String),
+    UnknownWorkspace(u64),
+}
+
+impl fmt::Display for CompositorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompositorError::NotRunning(name) => write!(f, "{} is not running", name),
+            CompositorError::Ipc(e) => write!(f, "Compositor IPC error: {}", e),
+            CompositorError::InvalidReply(e) => write!(f, "Invalid compositor reply: {}", e),
+            CompositorError::UnknownWorkspace(id) => write!(f, "Unknown workspace id {}", id),
+        }
+    }
+}
+
+impl Error for CompositorError {}
+
+/// Window position and size in logical pixels, relative to the output the window is on.
+/// `position` is `None` when the compositor does not expose it (niri tiled windows).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowGeometry {
+    pub position: Option<(f64, f64)>,
+    pub size: (f64, f64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowInfo {
+    pub id: u64,
+    pub title: Option<String>,
+    pub app_id: Option<String>,
+    pub pid: Option<i32>,
+    pub workspace_id: Option<u64>,
+    pub is_focused: bool,
+    pub is_floating: bool,
+    pub is_urgent: bool,
+    pub is_fullscreen: bool,
+    pub geometry: Option<WindowGeometry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceInfo {
+    pub id: u64,
+    pub idx: u32,
+    pub name: Option<String>,
+    pub output: Option<String>,
+    pub is_active: bool,
+    pub is_focused: bool,
+    pub is_urgent: bool,
+    pub active_window_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputInfo {
+    pub name: String,
+    pub make: String,
+    pub model: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompositorState {
+    pub connected: bool,
+    pub windows: HashMap<u64, WindowInfo>,
+    pub workspaces: HashMap<u64, WorkspaceInfo>,
+    pub outputs: HashMap<String, OutputInfo>,
+    pub focused_window_id: Option<u64>,
+}
+
+impl CompositorState {
+    pub fn focused_window(&self) -> Option<&WindowInfo> {
+        self.focused_window_id.and_then(|id| self.windows.get(&id))
+    }
+
+    pub fn workspaces_by_output(&self) -> Vec<(String, Vec<&WorkspaceInfo>)> {
+        let mut groups: Vec<(String, Vec<&WorkspaceInfo>)> = Vec::new();
+        for ws in self.workspaces.values() {
+            let output = ws.output.clone().unwrap_or_default();
+            match groups.iter_mut().find(|(name, _)| *name == output) {
+                Some((_, list)) => list.push(ws),
+                None => groups.push((output, vec![ws])),
+            }
+        }
+        for (_, list) in &mut groups {
+            list.sort_by_key(|ws| ws.idx);
+        }
+        groups.sort_by(|a, b| a.0.cmp(&b.0));
+        groups
+    }
+}
+
+pub trait Compositor: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn focused_window(&self) -> Result<Option<WindowInfo>, CompositorError>;
+    fn windows(&self) -> Result<Vec<WindowInfo>, CompositorError>;
+    fn workspaces(&self) -> Result<Vec<WorkspaceInfo>, CompositorError>;
+    fn outputs(&self) -> Result<Vec<OutputInfo>, CompositorError>;
+    fn focus_window(&self, id: u64) -> Result<(), CompositorError>;
+    fn close_window(&self, id: u64) -> Result<(), CompositorError>;
+    fn focus_workspace(&self, id: u64) -> Result<(), CompositorError>;
+    fn subscribe(&self) -> watch::Receiver<CompositorState>;
+}
+
+pub type StreamFn = fn(&watch::Sender<CompositorState>) -> Result<(), CompositorError>;
+
+pub struct StateStream {
+    name: &'static str,
+    tx: OnceLock<watch::Sender<CompositorState>>,
+    started: Once,
+}
+
+impl StateStream {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            tx: OnceLock::new(),
+            started: Once::new(),
+        }
+    }
+
+    pub fn subscribe(&'static self, stream_fn: StreamFn) -> watch::Receiver<CompositorState> {
+        let tx = self
+            .tx
+            .get_or_init(|| watch::channel(CompositorState::default()).0);
+        self.started.call_once(|| {
+            let spawn_result = thread::Builder::new()
+                .name(format!("{}-event-stream", self.name))
+                .spawn(move || self.run(tx, stream_fn));
+            if let Err(e) = spawn_result {
+                eprintln!("Failed to start {} event stream thread: {}", self.name, e);
+            }
+        });
+        tx.subscribe()
+    }
+
+    fn run(&self, tx: &watch::Sender<CompositorState>, stream_fn: StreamFn) {
+        let mut delay = RECONNECT_DELAY_MIN;
+        loop {
+            match stream_fn(tx) {
+                Ok(()) => eprintln!("{} event stream closed, reconnecting...", self.name),
+                Err(CompositorError::NotRunning(name)) => {
+                    eprintln!("{} is not running. Live window tracking disabled.", name);
+                    return;
+                }
+                Err(e) => eprintln!("{} event stream error: {}", self.name, e),
+            }
+
+            let was_connected = tx.send_if_modified(|state| {
+                let was_connected = state.connected;
+                *state = CompositorState::default();
+                was_connected
+            });
+            if was_connected {
+                delay = RECONNECT_DELAY_MIN;
+            }
+
+            thread::sleep(delay);
+            delay = (delay * 2).min(RECONNECT_DELAY_MAX);
+        }
+    }
+}
+
+/// Reads events from a compositor socket on its own thread so bursts can be
+/// collected and handled with a single refresh.
+pub struct EventReader<T> {
+    events: mpsc::Receiver<Result<T, CompositorError>>,
+    socket: UnixStream,
+}
+
+impl<T: Send + 'static> EventReader<T> {
+    /// `read_event` blocks for the next event and returns `None` once the
+    /// socket is closed.
+    pub fn spawn<F>(socket: &UnixStream, mut read_event: F) -> Result<Self, CompositorError>
+    where
+        F: FnMut() -> Result<Option<T>, CompositorError> + Send + 'static,
+    {
+        let socket = socket
+            .try_clone()
+            .map_err(|e| CompositorError::Ipc(e.to_string()))?;
+        let (tx, events) = mpsc::channel();
+        thread::Builder::new()
+            .name("compositor-event-reader".into())
+            .spawn(move || {
+                while let Some(event) = read_event().transpose() {
+                    let failed = event.is_err();
+                    if tx.send(event).is_err() || failed {
+                        break;
+                    }
+                }
+            })
+            .map_err(|e| CompositorError::Ipc(e.to_string()))?;
+        Ok(Self { events, socket })
+    }
+
+    /// Blocks for the next event, then collects the rest of its burst.
+    /// Returns `None` once the socket is closed.
+    pub fn next_burst(&self) -> Result<Option<Vec<T>>, CompositorError> {
+        let Ok(first) = self.events.recv() else {
+            return Ok(None);
+        };
+        let mut burst = vec![first?];
+        let deadline = Instant::now() + EVENT_COALESCE;
+        while let Ok(event) = self
+            .events
+            .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        {
+            burst.push(event?);
+        }
+        Ok(Some(burst))
+    }
+}
+
+impl<T> Drop for EventReader<T> {
+    fn drop(&mut self) {
+        // Unblocks the reader thread so it exits with the stream.
+        let _ = self.socket.shutdown(Shutdown::Both);
+    }
+}
+
+pub fn detect_compositor() -> CompositorKind {
+    if env::var_os("NIRI_SOCKET").is_some() {
+        CompositorKind::Niri
+    } else if env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        CompositorKind::Hyprland
+    } else if env::var_os("SWAYSOCK").is_some() {
+        CompositorKind::Sway
+    } else {
+        CompositorKind::Niri
+    }
+}
+
+fn create_compositor(kind: CompositorKind) -> Box<dyn Compositor> {
+    let kind = match kind {
+        CompositorKind::Auto => detect_compositor(),
+        other => other,
+    };
+    match kind {
+        CompositorKind::Auto | CompositorKind::Niri => Box::new(NiriCompositor),
+        CompositorKind::Hyprland => Box::new(HyprlandCompositor),
+        CompositorKind::Sway => Box::new(SwayCompositor),
+    }
+}
+
+pub fn init_compositor(kind: CompositorKind) {
+    if COMPOSITOR.set(create_compositor(kind)).is_err() {
+        eprintln!(
+            "Compositor backend already initialized, ignoring {:?}",
+            kind
+        );
+    }
+}
+
+pub fn compositor() -> &'static dyn Compositor {
+    COMPOSITOR
+        .get_or_init(|| create_compositor(CompositorKind::Auto))
+        .as_ref()
+}
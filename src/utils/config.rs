@@ -1,5 +1,7 @@
+use crate::utils::compositor::CompositorKind;
 use serde::{Deserialize, Serialize};
-use std::{error::Error, fs, path::PathBuf};
+use serde_yaml::Value;
+use std::{collections::HashMap, error::Error, fs, path::PathBuf};
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
 #[serde(rename_all = "kebab-case")]
@@ -10,6 +12,8 @@ pub enum ModuleType {
     Battery,
     Network,
     Display,
+    Workspaces,
+    Power,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
@@ -29,13 +33,20 @@ pub enum DockPosition {
     Bottom,
 }
 
+// Config keys are kebab-case. The snake_case aliases keep configs written
+// by older releases, which serialized the defaults with snake_case keys,
+// loading unchanged.
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
-#[serde(default)]
+#[serde(default, rename_all = "kebab-case")]
 pub struct BatteryConfig {
+    #[serde(alias = "show_details")]
     pub show_details: bool,
+    #[serde(alias = "show_power_profiles")]
     pub show_power_profiles: bool,
+    #[serde(alias = "show_conservation_mode")]
     pub show_conservation_mode: bool,
-    #[serde(rename = "conservation-mode-path")]
+    #[serde(alias = "conservation_mode_path")]
     pub conservation_mode_path: Option<PathBuf>,
 }
 
@@ -51,20 +62,34 @@ impl Default for BatteryConfig {
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
-#[serde(default)]
+#[serde(default, rename_all = "kebab-case")]
 pub struct NetworkConfig {}
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
-#[serde(default)]
+#[serde(default, rename_all = "kebab-case")]
 pub struct DockConfig {
     pub position: DockPosition,
+    #[serde(alias = "icon_size")]
     pub icon_size: u32,
+    #[serde(alias = "auto_hide")]
     pub auto_hide: bool,
+    pub intellihide: bool,
+    #[serde(alias = "show_running_apps")]
     pub show_running_apps: bool,
+    #[serde(alias = "show_favorites")]
     pub show_favorites: bool,
     pub favorites: Vec<String>,
+    #[serde(alias = "reveal_delay")]
     pub reveal_delay: u32,
+    #[serde(alias = "hide_delay")]
     pub hide_delay: u32,
+    pub magnification: f64,
+    pub outputs: Vec<String>,
+    /// The `outputs` entry whose dock lists its own favorites, which is
+    /// where pins made on that dock are saved. `None` means the top-level
+    /// `dock.favorites`.
+    #[serde(skip)]
+    pub favorites_output: Option<String>,
 }
 
 impl Default for DockConfig {
@@ -73,6 +98,7 @@ impl Default for DockConfig {
             position: DockPosition::Bottom,
             icon_size: 48,
             auto_hide: false,
+            intellihide: false,
             show_running_apps: true,
             show_favorites: true,
             favorites: vec![
@@ -83,31 +109,90 @@ impl Default for DockConfig {
             ],
             reveal_delay: 200,
             hide_delay: 1000,
+            magnification: 1.0,
+            outputs: vec![],
+            favorites_output: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SearchConfig {
+    #[serde(alias = "calculator_prefix")]
+    pub calculator_prefix: String,
+    #[serde(alias = "command_prefix")]
+    pub command_prefix: String,
+    #[serde(alias = "windows_prefix")]
+    pub windows_prefix: String,
+    #[serde(alias = "files_prefix")]
+    pub files_prefix: String,
+    #[serde(alias = "max_results")]
+    pub max_results: usize,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            calculator_prefix: "=".to_string(),
+            command_prefix: ">".to_string(),
+            windows_prefix: "@".to_string(),
+            files_prefix: "#".to_string(),
+            max_results: 5,
         }
     }
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct OutputConfig {
+    #[serde(alias = "modules_left")]
+    pub modules_left: Option<Vec<ModuleType>>,
+    #[serde(alias = "modules_center")]
+    pub modules_center: Option<Vec<ModuleType>>,
+    #[serde(alias = "modules_right")]
+    pub modules_right: Option<Vec<ModuleType>>,
+    #[serde(alias = "clock_format")]
+    pub clock_format: Option<String>,
+    pub dock: Option<DockConfig>,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
-#[serde(default)]
+#[serde(default, rename_all = "kebab-case")]
 pub struct BarConfig {
+    pub compositor: CompositorKind,
     pub font: Option<String>,
+    #[serde(alias = "bar_outputs")]
+    pub bar_outputs: Vec<String>,
+    #[serde(alias = "modules_left")]
     pub modules_left: Vec<ModuleType>,
+    #[serde(alias = "modules_center")]
     pub modules_center: Vec<ModuleType>,
+    #[serde(alias = "modules_right")]
     pub modules_right: Vec<ModuleType>,
+    #[serde(alias = "distro_icon_override")]
     pub distro_icon_override: Option<String>,
+    #[serde(alias = "clock_format")]
     pub clock_format: Option<String>,
+    #[serde(alias = "notification_position")]
     pub notification_position: NotificationPosition,
-    #[serde(rename = "active-client-max-length")]
+    #[serde(alias = "active_client_max_length")]
     pub active_client_max_length: usize,
     pub battery: BatteryConfig,
     pub network: NetworkConfig,
     pub dock: DockConfig,
+    pub search: SearchConfig,
+    #[serde(alias = "app_id_map")]
+    pub app_id_map: HashMap<String, String>,
+    pub outputs: HashMap<String, OutputConfig>,
 }
 
 impl Default for BarConfig {
     fn default() -> Self {
         Self {
+            compositor: CompositorKind::Auto,
             font: Some("Sans 10".to_string()),
+            bar_outputs: vec![],
             modules_left: vec![ModuleType::AppMenu, ModuleType::ActiveClient],
             modules_center: vec![],
             modules_right: vec![ModuleType::Battery, ModuleType::Clock],
@@ -118,7 +203,39 @@ impl Default for BarConfig {
             battery: BatteryConfig::default(),
             network: NetworkConfig::default(),
             dock: DockConfig::default(),
+            search: SearchConfig::default(),
+            app_id_map: HashMap::new(),
+            outputs: HashMap::new(),
+        }
+    }
+}
+
+impl BarConfig {
+    pub fn for_output(&self, connector: &str, model: Option<&str>) -> BarConfig {
+        let mut config = self.clone();
+        let overrides = self
+            .outputs
+            .get(connector)
+            .or_else(|| model.and_then(|model| self.outputs.get(model)));
+
+        if let Some(overrides) = overrides {
+            if let Some(modules) = &overrides.modules_left {
+                config.modules_left = modules.clone();
+            }
+            if let Some(modules) = &overrides.modules_center {
+                config.modules_center = modules.clone();
+            }
+            if let Some(modules) = &overrides.modules_right {
+                config.modules_right = modules.clone();
+            }
+            if let Some(format) = &overrides.clock_format {
+                config.clock_format = Some(format.clone());
+            }
+            if let Some(dock) = &overrides.dock {
+                config.dock = dock.clone();
+            }
         }
+        config
     }
 }
 
@@ -141,12 +258,97 @@ fn ensure_config_exists() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+fn kebab_key(key: &Value) -> Option<String> {
+    key.as_str().map(|key| key.replace('_', "-"))
+}
+
+fn inherit_output_dock(value: &mut Value) {
+    let Some(root) = value.as_mapping_mut() else {
+        return;
+    };
+    let Some(Value::Mapping(dock)) = root.get("dock").cloned() else {
+        return;
+    };
+    let Some(Value::Mapping(outputs)) = root.get_mut("outputs") else {
+        return;
+    };
+
+    for output in outputs.values_mut() {
+        if let Some(Value::Mapping(output_dock)) = output.get_mut("dock") {
+            for (key, default) in &dock {
+                // `icon_size` and `icon-size` name the same setting.
+                let overridden = output_dock
+                    .keys()
+                    .any(|output_key| kebab_key(output_key) == kebab_key(key));
+                if !overridden {
+                    output_dock.insert(key.clone(), default.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Dotted paths of keys in `raw` that have no counterpart in `known`, the
+/// loaded config serialized back. A key matches its kebab-case name or the
+/// snake_case alias of it.
+fn unknown_keys(raw: &Value, known: &Value, prefix: &str, unknown: &mut Vec<String>) {
+    let (Value::Mapping(raw), Value::Mapping(known)) = (raw, known) else {
+        return;
+    };
+    for (key, value) in raw {
+        let Some(name) = key.as_str() else {
+            continue;
+        };
+        let path = if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        let matched = known.iter().find(|(known_key, _)| {
+            known_key.as_str().is_some_and(|known_name| {
+                known_name == name || known_name.replace('-', "_") == name
+            })
+        });
+        match matched {
+            Some((_, known_value)) => unknown_keys(value, known_value, &path, unknown),
+            None => unknown.push(path),
+        }
+    }
+}
+
+/// Parses the config and lists the keys it ignored, so a typo costs one
+/// setting rather than the whole file.
+fn parse_config(s: &str) -> Result<(BarConfig, Vec<String>), Box<dyn Error>> {
+    let raw: Value = serde_yaml::from_str(s)?;
+    let mut value = raw.clone();
+    inherit_output_dock(&mut value);
+    let mut cfg: BarConfig = serde_yaml::from_value(value)?;
+
+    for (name, output) in cfg.outputs.iter_mut() {
+        let own_favorites = raw
+            .get("outputs")
+            .and_then(|outputs| outputs.get(name.as_str()))
+            .and_then(|output| output.get("dock"))
+            .is_some_and(|dock| dock.get("favorites").is_some());
+        if let (true, Some(dock)) = (own_favorites, output.dock.as_mut()) {
+            dock.favorites_output = Some(name.clone());
+        }
+    }
+
+    let mut unknown = Vec::new();
+    unknown_keys(&raw, &serde_yaml::to_value(&cfg)?, "", &mut unknown);
+    Ok((cfg, unknown))
+}
+
 pub fn load_config() -> BarConfig {
     fn load() -> Result<BarConfig, Box<dyn Error>> {
         ensure_config_exists()?;
         let path = get_config_path()?;
         let s = fs::read_to_string(&path)?;
-        let cfg: BarConfig = serde_yaml::from_str(&s)?;
+        let (cfg, unknown) = parse_config(&s)?;
+        for key in unknown {
+            eprintln!("Ignoring unknown config key `{}`", key);
+        }
         Ok(cfg)
     }
     load().unwrap_or_else(|e| {
@@ -154,3 +356,314 @@ pub fn load_config() -> BarConfig {
         BarConfig::default()
     })
 }
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn is_content(line: &str) -> bool {
+    let line = line.trim_start();
+    !line.is_empty() && !line.starts_with('#')
+}
+
+/// The inline value of `key` if `line`, stripped of its indent, is that key.
+fn key_value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    [
+        key.to_string(),
+        format!("\"{}\"", key),
+        format!("'{}'", key),
+    ]
+    .iter()
+    .find_map(|name| line.strip_prefix(name.as_str())?.strip_prefix(':'))
+    .filter(|rest| rest.is_empty() || rest.starts_with(' '))
+    .map(|rest| rest.trim())
+    .map(|rest| if rest.starts_with('#') { "" } else { rest })
+}
+
+fn yaml_scalar(value: &str) -> Result<String, Box<dyn Error>> {
+    Ok(serde_yaml::to_string(value)?.trim_end().to_string())
+}
+
+/// One past the last content line of the value of the key on line `key`.
+/// Comments after it are left to whatever follows.
+fn block_end(lines: &[String], key: usize) -> usize {
+    let indent = indent_of(&lines[key]);
+    let mut end = lines[key + 1..]
+        .iter()
+        .position(|line| {
+            is_content(line)
+                && (indent_of(line) < indent
+                    || (indent_of(line) == indent && !line.trim_start().starts_with('-')))
+        })
+        .map_or(lines.len(), |offset| key + 1 + offset);
+    while end > key + 1 && !is_content(&lines[end - 1]) {
+        end -= 1;
+    }
+    end
+}
+
+/// Replaces the list at `path` in `text` line by line, so comments, key
+/// order and formatting everywhere else survive. Missing keys are added at
+/// the end of their parent.
+fn set_yaml_list(text: &str, path: &[&str], items: &[String]) -> Result<String, Box<dyn Error>> {
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    let (mut start, mut end) = (0, lines.len());
+    while end > 0 && !is_content(&lines[end - 1]) {
+        end -= 1;
+    }
+    let mut indent = 0;
+
+    for (depth, key) in path.iter().enumerate() {
+        indent = lines[start..end]
+            .iter()
+            .find(|line| is_content(line))
+            .map_or(indent, |line| indent_of(line));
+        let found = (start..end).find(|&i| {
+            is_content(&lines[i])
+                && indent_of(&lines[i]) == indent
+                && key_value(lines[i].trim_start(), key).is_some()
+        });
+
+        let Some(line) = found else {
+            let mut added = Vec::new();
+            for (offset, key) in path[depth..].iter().enumerate() {
+                let pad = " ".repeat(indent + offset * 2);
+                added.push(format!("{}{}:", pad, yaml_scalar(key)?));
+            }
+            let pad = " ".repeat(indent + (path.len() - depth - 1) * 2);
+            added.pop();
+            added.extend(list_lines(&pad, path[path.len() - 1], items)?);
+            lines.splice(end..end, added);
+            break;
+        };
+
+        let block = block_end(&lines, line);
+        if depth == path.len() - 1 {
+            let pad = " ".repeat(indent);
+            lines.splice(line..block, list_lines(&pad, key, items)?);
+            break;
+        }
+        if !key_value(lines[line].trim_start(), key).is_some_and(str::is_empty) {
+            let path = path[..=depth].join(".");
+            return Err(format!("Config key `{}` is not a block mapping", path).into());
+        }
+        (start, end) = (line + 1, block);
+        indent += 2;
+    }
+
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+
+    // Refuse to write anything that doesn't read back as the intended list.
+    let value: Value = serde_yaml::from_str(&updated)?;
+    let written = path.iter().try_fold(&value, |value, key| value.get(key));
+    if written != Some(&serde_yaml::to_value(items)?) {
+        return Err(format!("Could not update `{}` in place", path.join(".")).into());
+    }
+    Ok(updated)
+}
+
+fn list_lines(pad: &str, key: &str, items: &[String]) -> Result<Vec<String>, Box<dyn Error>> {
+    let key = yaml_scalar(key)?;
+    if items.is_empty() {
+        return Ok(vec![format!("{}{}: []", pad, key)]);
+    }
+    let mut lines = vec![format!("{}{}:", pad, key)];
+    for item in items {
+        lines.push(format!("{}  - {}", pad, yaml_scalar(item)?));
+    }
+    Ok(lines)
+}
+
+/// Saves the dock favorites to `outputs.<output>.dock.favorites`, or to the
+/// top-level `dock.favorites` when `output` is `None`, editing just that key.
+pub fn save_dock_favorites(
+    output: Option<&str>,
+    favorites: &[String],
+) -> Result<(), Box<dyn Error>> {
+    ensure_config_exists()?;
+    let path = get_config_path()?;
+    let keys = match output {
+        Some(output) => vec!["outputs", output, "dock", "favorites"],
+        None => vec!["dock", "favorites"],
+    };
+    let s = fs::read_to_string(&path)?;
+    fs::write(&path, set_yaml_list(&s, &keys, favorites)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_kebab_case_keys() {
+        let (cfg, unknown) = parse_config(
+            "modules-left: [clock]\n\
+             active-client-max-length: 30\n\
+             dock:\n  icon-size: 32\n  show-running-apps: false\n",
+        )
+        .unwrap();
+        assert!(unknown.is_empty());
+        assert_eq!(cfg.modules_left, vec![ModuleType::Clock]);
+        assert_eq!(cfg.active_client_max_length, 30);
+        assert_eq!(cfg.dock.icon_size, 32);
+        assert!(!cfg.dock.show_running_apps);
+    }
+
+    #[test]
+    fn reads_snake_case_keys_from_older_configs() {
+        let (cfg, unknown) = parse_config(
+            "modules_left: [clock]\n\
+             notification_position: bottom-left\n\
+             battery:\n  show_details: false\n\
+             dock:\n  icon_size: 32\n  reveal_delay: 50\n\
+             outputs:\n  HDMI-A-1:\n    dock:\n      auto-hide: true\n      icon-size: 24\n",
+        )
+        .unwrap();
+        assert!(unknown.is_empty());
+        assert_eq!(cfg.modules_left, vec![ModuleType::Clock]);
+        assert_eq!(cfg.notification_position, NotificationPosition::BottomLeft);
+        assert!(!cfg.battery.show_details);
+        assert_eq!(cfg.dock.icon_size, 32);
+        assert_eq!(cfg.dock.reveal_delay, 50);
+        let output = cfg.for_output("HDMI-A-1", None);
+        assert!(output.dock.auto_hide);
+        assert_eq!(output.dock.icon_size, 24);
+        assert_eq!(output.dock.reveal_delay, 50);
+    }
+
+    #[test]
+    fn unknown_keys_are_reported_without_resetting_the_rest() {
+        let (cfg, unknown) = parse_config(
+            "clock-format: \"%H:%M\"\n\
+             clock-fromat: \"%H\"\n\
+             dock:\n  icon-size: 32\n  icon-sise: 40\n\
+             outputs:\n  HDMI-A-1:\n    dock:\n      autohide: true\n\
+             app-id-map:\n  steam_app_1: hades\n",
+        )
+        .unwrap();
+        assert_eq!(
+            unknown,
+            vec![
+                "clock-fromat",
+                "dock.icon-sise",
+                "outputs.HDMI-A-1.dock.autohide"
+            ]
+        );
+        assert_eq!(cfg.clock_format.as_deref(), Some("%H:%M"));
+        assert_eq!(cfg.dock.icon_size, 32);
+        assert_eq!(cfg.app_id_map["steam_app_1"], "hades");
+    }
+
+    #[test]
+    fn generated_default_config_round_trips() {
+        let yaml = serde_yaml::to_string(&BarConfig::default()).unwrap();
+        assert!(yaml.contains("modules-left"));
+        let (_, unknown) = parse_config(&yaml).unwrap();
+        assert!(unknown.is_empty());
+    }
+
+    fn list(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    const CONFIG: &str = "\
+# Top comment
+dock:
+  icon-size: 48
+  favorites:
+    - zen
+    - firefox
+  # Icon scale on hover
+  magnification: 1.0
+
+outputs:
+  \"HDMI-A-1\":
+    dock:
+      favorites: [code]
+      auto-hide: true
+  eDP-1:
+    clock-format: \"%H:%M\"
+# Trailing comment
+";
+
+    #[test]
+    fn saving_favorites_edits_only_that_list() {
+        let updated = set_yaml_list(
+            CONFIG,
+            &["dock", "favorites"],
+            &list(&["firefox", "zen", "foot"]),
+        )
+        .unwrap();
+        assert_eq!(
+            updated,
+            CONFIG.replace(
+                "    - zen\n    - firefox\n",
+                "    - firefox\n    - zen\n    - foot\n"
+            )
+        );
+    }
+
+    #[test]
+    fn saving_output_favorites_leaves_the_top_level_list_alone() {
+        let updated = set_yaml_list(
+            CONFIG,
+            &["outputs", "HDMI-A-1", "dock", "favorites"],
+            &list(&["code", "zen"]),
+        )
+        .unwrap();
+        assert_eq!(
+            updated,
+            CONFIG.replace(
+                "      favorites: [code]\n",
+                "      favorites:\n        - code\n        - zen\n"
+            )
+        );
+
+        let emptied =
+            set_yaml_list(CONFIG, &["outputs", "HDMI-A-1", "dock", "favorites"], &[]).unwrap();
+        assert_eq!(
+            emptied,
+            CONFIG.replace("favorites: [code]", "favorites: []")
+        );
+    }
+
+    #[test]
+    fn saving_favorites_adds_missing_keys() {
+        let updated = set_yaml_list(
+            CONFIG,
+            &["outputs", "eDP-1", "dock", "favorites"],
+            &list(&["zen"]),
+        )
+        .unwrap();
+        assert_eq!(
+            updated,
+            CONFIG.replace(
+                "    clock-format: \"%H:%M\"\n",
+                "    clock-format: \"%H:%M\"\n    dock:\n      favorites:\n        - zen\n"
+            )
+        );
+
+        let created = set_yaml_list("", &["dock", "favorites"], &list(&["zen"])).unwrap();
+        assert_eq!(created, "dock:\n  favorites:\n    - zen\n");
+    }
+
+    #[test]
+    fn saving_favorites_understands_generated_configs() {
+        let generated = serde_yaml::to_string(&BarConfig::default()).unwrap();
+        let updated = set_yaml_list(&generated, &["dock", "favorites"], &list(&["foot"])).unwrap();
+        let (cfg, _) = parse_config(&updated).unwrap();
+        assert_eq!(cfg.dock.favorites, vec!["foot"]);
+        assert_eq!(cfg.dock.icon_size, BarConfig::default().dock.icon_size);
+    }
+
+    #[test]
+    fn outputs_with_their_own_favorites_are_marked() {
+        let (cfg, _) = parse_config(CONFIG).unwrap();
+        assert_eq!(cfg.dock.favorites_output, None);
+        let hdmi = cfg.for_output("HDMI-A-1", None);
+        assert_eq!(hdmi.dock.favorites_output.as_deref(), Some("HDMI-A-1"));
+        assert_eq!(hdmi.dock.favorites, vec!["code"]);
+    }
+}
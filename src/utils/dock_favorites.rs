@@ -0,0 +1,132 @@
+use crate::utils::config::{save_dock_favorites, DockConfig};
+use std::{collections::HashMap, sync::OnceLock};
+use tokio::sync::watch;
+
+static FAVORITES: OnceLock<watch::Sender<Favorites>> = OnceLock::new();
+
+/// Pinned apps shared by every dock, keyed by the `outputs` entry whose dock
+/// lists its own favorites, or `None` for the top-level `dock.favorites`.
+pub type Favorites = HashMap<Option<String>, Vec<String>>;
+
+fn sender() -> &'static watch::Sender<Favorites> {
+    FAVORITES.get_or_init(|| watch::channel(Favorites::new()).0)
+}
+
+/// Subscribes to the shared favorites. The first dock using a list seeds it
+/// from `config`; later ones, such as docks for hotplugged outputs, keep the
+/// live list since their config may predate pins made since startup.
+pub fn subscribe(config: &DockConfig) -> watch::Receiver<Favorites> {
+    sender().send_if_modified(|favorites| {
+        favorites
+            .entry(config.favorites_output.clone())
+            .or_insert_with(|| config.favorites.clone());
+        // Only the subscribing dock shows a newly seeded list.
+        false
+    });
+    sender().subscribe()
+}
+
+/// The list a dock with `config` shows.
+pub fn get(favorites: &Favorites, config: &DockConfig) -> Vec<String> {
+    favorites
+        .get(&config.favorites_output)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Saves `list` where `config`'s favorites came from and updates every dock
+/// showing it.
+pub fn set(config: &DockConfig, list: Vec<String>) {
+    if let Err(e) = save_dock_favorites(config.favorites_output.as_deref(), &list) {
+        eprintln!("Failed to save dock favorites: {}", e);
+    }
+    sender().send_if_modified(|favorites| {
+        let current = favorites
+            .entry(config.favorites_output.clone())
+            .or_default();
+        if *current == list {
+            return false;
+        }
+        *current = list;
+        true
+    });
+}
+
+/// `list` with `pin_id` moved before or after `target`, or to the end when
+/// there is no target or it isn't pinned.
+fn reorder(list: &[String], pin_id: &str, target: Option<(&str, bool)>) -> Vec<String> {
+    if target.is_some_and(|(target_id, _)| target_id == pin_id) {
+        return list.to_vec();
+    }
+    let mut list: Vec<String> = list.iter().filter(|f| *f != pin_id).cloned().collect();
+    let index = target
+        .and_then(|(target_id, after)| {
+            list.iter()
+                .position(|f| f == target_id)
+                .map(|i| if after { i + 1 } else { i })
+        })
+        .unwrap_or(list.len());
+    list.insert(index, pin_id.to_string());
+    list
+}
+
+/// Pins `pin_id`, or moves it if already pinned, next to `target` in the
+/// live list, where `target` is a pinned app and whether to go after it.
+/// Reordering the shared list rather than a dock's copy keeps a drop from
+/// undoing a change another dock just made.
+pub fn move_to(config: &DockConfig, pin_id: &str, target: Option<(&str, bool)>) {
+    let current = get(&sender().borrow(), config);
+    let list = reorder(&current, pin_id, target);
+    if list != current {
+        set(config, list);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn reorders_around_the_drop_target() {
+        let favorites = list(&["zen", "firefox", "foot"]);
+        assert_eq!(
+            reorder(&favorites, "foot", Some(("zen", false))),
+            vec!["foot", "zen", "firefox"]
+        );
+        assert_eq!(
+            reorder(&favorites, "zen", Some(("firefox", true))),
+            vec!["firefox", "zen", "foot"]
+        );
+        assert_eq!(
+            reorder(&favorites, "zen", Some(("foot", true))),
+            vec!["firefox", "foot", "zen"]
+        );
+    }
+
+    #[test]
+    fn unpinned_apps_are_pinned_where_dropped() {
+        let favorites = list(&["zen", "firefox"]);
+        assert_eq!(
+            reorder(&favorites, "code", Some(("firefox", false))),
+            vec!["zen", "code", "firefox"]
+        );
+        assert_eq!(
+            reorder(&favorites, "code", None),
+            vec!["zen", "firefox", "code"]
+        );
+        assert_eq!(
+            reorder(&favorites, "code", Some(("missing", true))),
+            vec!["zen", "firefox", "code"]
+        );
+    }
+
+    #[test]
+    fn dropping_onto_itself_changes_nothing() {
+        let favorites = list(&["zen", "firefox"]);
+        assert_eq!(reorder(&favorites, "zen", Some(("zen", true))), favorites);
+    }
+}
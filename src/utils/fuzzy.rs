@@ -0,0 +1,53 @@
+const MATCH_SCORE: i32 = 16;
+const CONSECUTIVE_BONUS: i32 = 12;
+const WORD_START_BONUS: i32 = 10;
+const FIRST_CHAR_BONUS: i32 = 8;
+const GAP_PENALTY: i32 = 1;
+const MAX_GAP_PENALTY: usize = 10;
+
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let candidate: Vec<char> = candidate.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut query_pos = 0;
+    let mut last_match: Option<usize> = None;
+
+    for (i, c) in candidate.iter().enumerate() {
+        if query_pos == query.len() {
+            break;
+        }
+        if *c != query[query_pos] {
+            continue;
+        }
+
+        score += MATCH_SCORE;
+        if i == 0 {
+            score += FIRST_CHAR_BONUS;
+        }
+        if i > 0 && !candidate[i - 1].is_alphanumeric() {
+            score += WORD_START_BONUS;
+        }
+        match last_match {
+            Some(last) if last + 1 == i => score += CONSECUTIVE_BONUS,
+            Some(last) => score -= (i - last - 1).min(MAX_GAP_PENALTY) as i32 * GAP_PENALTY,
+            None => score -= i.min(MAX_GAP_PENALTY) as i32 * GAP_PENALTY,
+        }
+
+        last_match = Some(i);
+        query_pos += 1;
+    }
+
+    if query_pos == query.len() {
+        Some(score)
+    } else {
+        None
+    }
+}
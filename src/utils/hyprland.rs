@@ -0,0 +1,572 @@
+use crate::utils::compositor::{
+    Compositor, CompositorError, CompositorState, EventReader, OutputInfo, StateStream,
+    WindowGeometry, WindowInfo, WorkspaceInfo,
+};
+use serde::Deserialize;
+use std::{
+    collections::HashSet,
+    env,
+    io::{BufRead, BufReader, Read, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+};
+use tokio::sync::watch;
+
+const INSTANCE_SIGNATURE_ENV: &str = "HYPRLAND_INSTANCE_SIGNATURE";
+const COMMAND_SOCKET: &str = ".socket.sock";
+const EVENT_SOCKET: &str = ".socket2.sock";
+
+static STATE_STREAM: StateStream = StateStream::new("Hyprland");
+
+#[derive(Debug, Deserialize)]
+struct HyprWorkspaceRef {
+    id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HyprClient {
+    address: String,
+    #[serde(default = "default_true")]
+    mapped: bool,
+    workspace: HyprWorkspaceRef,
+    #[serde(default)]
+    floating: bool,
+    #[serde(default)]
+    class: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    pid: i32,
+    #[serde(default)]
+    at: [i32; 2],
+    #[serde(default)]
+    size: [i32; 2],
+    #[serde(default)]
+    monitor: i64,
+    // A bool before Hyprland 0.41, a fullscreen mode number since.
+    #[serde(default)]
+    fullscreen: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HyprWorkspace {
+    id: i64,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    monitor: String,
+    #[serde(default)]
+    lastwindow: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HyprMonitor {
+    #[serde(default)]
+    id: i64,
+    name: String,
+    #[serde(default)]
+    make: String,
+    #[serde(default)]
+    model: String,
+    #[serde(default)]
+    width: i32,
+    #[serde(default)]
+    height: i32,
+    #[serde(default)]
+    x: i32,
+    #[serde(default)]
+    y: i32,
+    #[serde(default = "default_scale")]
+    scale: f64,
+    active_workspace: HyprWorkspaceRef,
+    #[serde(default)]
+    focused: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+fn socket_path(socket: &str) -> Result<PathBuf, CompositorError> {
+    let signature =
+        env::var(INSTANCE_SIGNATURE_ENV).map_err(|_| CompositorError::NotRunning("Hyprland"))?;
+
+    let mut candidates = Vec::new();
+    if let Some(runtime_dir) = env::var_os("XDG_RUNTIME_DIR") {
+        candidates.push(PathBuf::from(runtime_dir).join("hypr").join(&signature));
+    }
+    candidates.push(PathBuf::from("/tmp/hypr").join(&signature));
+
+    candidates
+        .into_iter()
+        .map(|dir| dir.join(socket))
+        .find(|path| path.exists())
+        .ok_or(CompositorError::NotRunning("Hyprland"))
+}
+
+fn send_command(command: &str) -> Result<String, CompositorError> {
+    let mut stream = UnixStream::connect(socket_path(COMMAND_SOCKET)?)
+        .map_err(|e| CompositorError::Ipc(e.to_string()))?;
+    stream
+        .write_all(command.as_bytes())
+        .map_err(|e| CompositorError::Ipc(e.to_string()))?;
+
+    let mut reply = String::new();
+    stream
+        .read_to_string(&mut reply)
+        .map_err(|e| CompositorError::Ipc(e.to_string()))?;
+    Ok(reply)
+}
+
+fn query<T: for<'de> Deserialize<'de>>(command: &str) -> Result<T, CompositorError> {
+    let reply = send_command(&format!("j/{}", command))?;
+    serde_json::from_str(&reply).map_err(|e| CompositorError::InvalidReply(e.to_string()))
+}
+
+fn dispatch(dispatcher: &str) -> Result<(), CompositorError> {
+    let reply = send_command(&format!("dispatch {}", dispatcher))?;
+    if reply.trim() == "ok" {
+        Ok(())
+    } else {
+        Err(CompositorError::Ipc(reply.trim().to_string()))
+    }
+}
+
+fn parse_address(address: &str) -> Option<u64> {
+    u64::from_str_radix(address.trim().trim_start_matches("0x"), 16).ok()
+}
+
+fn active_window_address() -> Result<Option<u64>, CompositorError> {
+    let active: serde_json::Value = query("activewindow")?;
+    Ok(active
+        .get("address")
+        .and_then(|a| a.as_str())
+        .and_then(parse_address))
+}
+
+/// Which replies an event may have invalidated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Refresh {
+    clients: bool,
+    workspaces: bool,
+    monitors: bool,
+}
+
+impl Refresh {
+    const NONE: Refresh = Refresh {
+        clients: false,
+        workspaces: false,
+        monitors: false,
+    };
+    const ALL: Refresh = Refresh {
+        clients: true,
+        workspaces: true,
+        monitors: true,
+    };
+
+    fn merge(&mut self, other: Refresh) {
+        self.clients |= other.clients;
+        self.workspaces |= other.workspaces;
+        self.monitors |= other.monitors;
+    }
+}
+
+/// The last replies from Hyprland, kept between events so each event only
+/// re-queries what it can have changed.
+#[derive(Debug, Default)]
+struct Snapshot {
+    clients: Vec<HyprClient>,
+    workspaces: Vec<HyprWorkspace>,
+    monitors: Vec<HyprMonitor>,
+    focused: Option<u64>,
+    urgent: HashSet<u64>,
+}
+
+impl Snapshot {
+    fn query(refresh: Refresh) -> Result<Self, CompositorError> {
+        let mut snapshot = Snapshot::default();
+        if refresh.clients {
+            snapshot.focused = active_window_address()?;
+        }
+        snapshot.refresh(refresh)?;
+        Ok(snapshot)
+    }
+
+    fn refresh(&mut self, refresh: Refresh) -> Result<(), CompositorError> {
+        if refresh.clients {
+            self.clients = query("clients")?;
+        }
+        if refresh.workspaces {
+            self.workspaces = query("workspaces")?;
+        }
+        if refresh.monitors {
+            self.monitors = query("monitors")?;
+        }
+        Ok(())
+    }
+
+    /// Applies what a socket2 event carries and returns what must be re-queried.
+    fn apply_event(&mut self, event: &str, data: &str) -> Refresh {
+        match event {
+            "urgent" => {
+                self.urgent.extend(parse_address(data));
+                Refresh::NONE
+            }
+            "activewindowv2" => {
+                self.focused = parse_address(data).filter(|addr| *addr != 0);
+                if let Some(id) = self.focused {
+                    self.urgent.remove(&id);
+                }
+                // Only a workspace's last focused window changes.
+                Refresh {
+                    workspaces: true,
+                    ..Refresh::NONE
+                }
+            }
+            "closewindow" | "openwindow" | "movewindow" | "movewindowv2" => {
+                if event == "closewindow" {
+                    if let Some(id) = parse_address(data) {
+                        self.urgent.remove(&id);
+                    }
+                }
+                Refresh {
+                    clients: true,
+                    workspaces: true,
+                    ..Refresh::NONE
+                }
+            }
+            "workspace" | "workspacev2" | "focusedmon" | "focusedmonv2" | "createworkspace"
+            | "createworkspacev2" | "destroyworkspace" | "destroyworkspacev2"
+            | "renameworkspace" | "activespecial" | "activespecialv2" => Refresh {
+                workspaces: true,
+                monitors: true,
+                ..Refresh::NONE
+            },
+            "moveworkspace" | "moveworkspacev2" | "monitoradded" | "monitoraddedv2"
+            | "monitorremoved" | "monitorremovedv2" | "configreloaded" => Refresh::ALL,
+            "activewindow" | "activelayout" | "submap" | "openlayer" | "closelayer"
+            | "screencast" | "bell" => Refresh::NONE,
+            // Title, floating, fullscreen, pin and group changes all show up in `clients`.
+            _ => Refresh {
+                clients: true,
+                ..Refresh::NONE
+            },
+        }
+    }
+
+    fn windows(&self) -> Vec<WindowInfo> {
+        self.clients
+            .iter()
+            .filter_map(|client| window_info(client, &self.monitors, self.focused, &self.urgent))
+            .collect()
+    }
+
+    fn workspaces(&self, windows: &[WindowInfo]) -> Vec<WorkspaceInfo> {
+        self.workspaces
+            .iter()
+            .filter(|ws| ws.id >= 0)
+            .map(|ws| {
+                let monitor = self.monitors.iter().find(|m| m.name == ws.monitor);
+                let is_active = monitor.is_some_and(|m| m.active_workspace.id == ws.id);
+                let id = ws.id as u64;
+                WorkspaceInfo {
+                    id,
+                    idx: ws.id as u32,
+                    name: Some(ws.name.clone()).filter(|name| *name != ws.id.to_string()),
+                    output: Some(ws.monitor.clone()).filter(|m| !m.is_empty()),
+                    is_active,
+                    is_focused: is_active && monitor.is_some_and(|m| m.focused),
+                    is_urgent: windows
+                        .iter()
+                        .any(|w| w.workspace_id == Some(id) && w.is_urgent),
+                    active_window_id: parse_address(&ws.lastwindow).filter(|addr| *addr != 0),
+                }
+            })
+            .collect()
+    }
+
+    fn outputs(&self) -> Vec<OutputInfo> {
+        self.monitors
+            .iter()
+            .map(|m| OutputInfo {
+                width: (m.width as f64 / m.scale).round() as u32,
+                height: (m.height as f64 / m.scale).round() as u32,
+                name: m.name.clone(),
+                make: m.make.clone(),
+                model: m.model.clone(),
+                x: m.x,
+                y: m.y,
+                scale: m.scale,
+            })
+            .collect()
+    }
+
+    fn to_state(&self) -> CompositorState {
+        let windows = self.windows();
+        let workspaces = self.workspaces(&windows);
+        CompositorState {
+            connected: true,
+            focused_window_id: windows.iter().find(|w| w.is_focused).map(|w| w.id),
+            windows: windows.into_iter().map(|w| (w.id, w)).collect(),
+            workspaces: workspaces.into_iter().map(|ws| (ws.id, ws)).collect(),
+            outputs: self
+                .outputs()
+                .into_iter()
+                .map(|o| (o.name.clone(), o))
+                .collect(),
+        }
+    }
+}
+
+fn window_info(
+    client: &HyprClient,
+    monitors: &[HyprMonitor],
+    focused: Option<u64>,
+    urgent: &HashSet<u64>,
+) -> Option<WindowInfo> {
+    let id = parse_address(&client.address)?;
+    if !client.mapped || client.workspace.id < 0 {
+        return None;
+    }
+    let (origin_x, origin_y) = monitors
+        .iter()
+        .find(|m| m.id == client.monitor)
+        .map_or((0, 0), |m| (m.x, m.y));
+    Some(WindowInfo {
+        id,
+        title: Some(client.title.clone()),
+        app_id: Some(client.class.clone()).filter(|c| !c.is_empty()),
+        pid: Some(client.pid).filter(|pid| *pid > 0),
+        workspace_id: Some(client.workspace.id as u64),
+        is_focused: focused == Some(id),
+        is_floating: client.floating,
+        is_urgent: urgent.contains(&id),
+        is_fullscreen: client.fullscreen.as_bool().unwrap_or(false)
+            || client.fullscreen.as_i64().is_some_and(|mode| mode > 0),
+        geometry: Some(WindowGeometry {
+            position: Some((
+                (client.at[0] - origin_x) as f64,
+                (client.at[1] - origin_y) as f64,
+            )),
+            size: (client.size[0] as f64, client.size[1] as f64),
+        }),
+    })
+}
+
+fn read_event_stream(tx: &watch::Sender<CompositorState>) -> Result<(), CompositorError> {
+    let stream = UnixStream::connect(socket_path(EVENT_SOCKET)?)
+        .map_err(|e| CompositorError::Ipc(e.to_string()))?;
+    let mut reader = BufReader::new(
+        stream
+            .try_clone()
+            .map_err(|e| CompositorError::Ipc(e.to_string()))?,
+    );
+    let events = EventReader::spawn(&stream, move || {
+        let mut line = String::new();
+        match reader.read_line(&mut line) {
+            Ok(0) => Ok(None),
+            Ok(_) => Ok(Some(line)),
+            Err(e) => Err(CompositorError::Ipc(e.to_string())),
+        }
+    })?;
+
+    let mut snapshot = Snapshot::query(Refresh::ALL)?;
+    tx.send_replace(snapshot.to_state());
+
+    while let Some(burst) = events.next_burst()? {
+        let mut refresh = Refresh::NONE;
+        for line in &burst {
+            let line = line.trim_end();
+            let (event, data) = line.split_once(">>").unwrap_or((line, ""));
+            refresh.merge(snapshot.apply_event(event, data));
+        }
+        snapshot.refresh(refresh)?;
+
+        let state = snapshot.to_state();
+        tx.send_if_modified(|current| {
+            if *current == state {
+                false
+            } else {
+                *current = state;
+                true
+            }
+        });
+    }
+    Ok(())
+}
+
+pub struct HyprlandCompositor;
+
+impl Compositor for HyprlandCompositor {
+    fn name(&self) -> &'static str {
+        "Hyprland"
+    }
+
+    fn focused_window(&self) -> Result<Option<WindowInfo>, CompositorError> {
+        Ok(self.windows()?.into_iter().find(|w| w.is_focused))
+    }
+
+    fn windows(&self) -> Result<Vec<WindowInfo>, CompositorError> {
+        let snapshot = Snapshot::query(Refresh {
+            clients: true,
+            monitors: true,
+            ..Refresh::NONE
+        })?;
+        Ok(snapshot.windows())
+    }
+
+    fn workspaces(&self) -> Result<Vec<WorkspaceInfo>, CompositorError> {
+        let snapshot = Snapshot::query(Refresh {
+            workspaces: true,
+            monitors: true,
+            ..Refresh::NONE
+        })?;
+        Ok(snapshot.workspaces(&[]))
+    }
+
+    fn outputs(&self) -> Result<Vec<OutputInfo>, CompositorError> {
+        let snapshot = Snapshot::query(Refresh {
+            monitors: true,
+            ..Refresh::NONE
+        })?;
+        Ok(snapshot.outputs())
+    }
+
+    fn focus_window(&self, id: u64) -> Result<(), CompositorError> {
+        dispatch(&format!("focuswindow address:0x{:x}", id))
+    }
+
+    fn close_window(&self, id: u64) -> Result<(), CompositorError> {
+        dispatch(&format!("closewindow address:0x{:x}", id))
+    }
+
+    fn focus_workspace(&self, id: u64) -> Result<(), CompositorError> {
+        dispatch(&format!("workspace {}", id))
+    }
+
+    fn subscribe(&self) -> watch::Receiver<CompositorState> {
+        STATE_STREAM.subscribe(read_event_stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Trimmed `hyprctl -j` replies from a two-monitor session.
+    const MONITORS: &str = r#"[
+        {"id": 0, "name": "eDP-1", "make": "BOE", "model": "0x0BCA", "width": 2880,
+         "height": 1800, "x": 0, "y": 0, "scale": 2.0,
+         "activeWorkspace": {"id": 1, "name": "1"}, "focused": true},
+        {"id": 1, "name": "HDMI-A-1", "make": "Dell Inc.", "model": "DELL U2720Q",
+         "width": 2560, "height": 1440, "x": 1440, "y": 0, "scale": 1.0,
+         "activeWorkspace": {"id": 3, "name": "3"}, "focused": false}
+    ]"#;
+
+    const WORKSPACES: &str = r#"[
+        {"id": 1, "name": "1", "monitor": "eDP-1", "windows": 1,
+         "lastwindow": "0x55d1c0a3e0f0", "lastwindowtitle": "kitty"},
+        {"id": 3, "name": "web", "monitor": "HDMI-A-1", "windows": 1,
+         "lastwindow": "0x55d1c0b41230", "lastwindowtitle": "Firefox"},
+        {"id": -98, "name": "special:scratch", "monitor": "eDP-1", "windows": 1,
+         "lastwindow": "0x55d1c0c00000", "lastwindowtitle": "btop"}
+    ]"#;
+
+    const CLIENTS: &str = r#"[
+        {"address": "0x55d1c0a3e0f0", "mapped": true, "at": [10, 10], "size": [1420, 880],
+         "workspace": {"id": 1, "name": "1"}, "floating": false, "monitor": 0,
+         "class": "kitty", "title": "kitty", "pid": 4242, "fullscreen": 0},
+        {"address": "0x55d1c0b41230", "mapped": true, "at": [1440, 0], "size": [2560, 1440],
+         "workspace": {"id": 3, "name": "web"}, "floating": false, "monitor": 1,
+         "class": "firefox", "title": "Firefox", "pid": 5151, "fullscreen": 2},
+        {"address": "0x55d1c0c00000", "mapped": true, "at": [200, 200], "size": [800, 500],
+         "workspace": {"id": -98, "name": "special:scratch"}, "floating": true, "monitor": 0,
+         "class": "btop", "title": "btop", "pid": 6161, "fullscreen": false}
+    ]"#;
+
+    fn snapshot() -> Snapshot {
+        Snapshot {
+            clients: serde_json::from_str(CLIENTS).unwrap(),
+            workspaces: serde_json::from_str(WORKSPACES).unwrap(),
+            monitors: serde_json::from_str(MONITORS).unwrap(),
+            focused: Some(0x55d1c0a3e0f0),
+            urgent: HashSet::new(),
+        }
+    }
+
+    #[test]
+    fn builds_state_from_captured_replies() {
+        let state = snapshot().to_state();
+
+        // The special workspace and its window are hidden.
+        assert_eq!(state.windows.len(), 2);
+        assert_eq!(state.workspaces.len(), 2);
+        assert_eq!(state.focused_window_id, Some(0x55d1c0a3e0f0));
+
+        let firefox = &state.windows[&0x55d1c0b41230];
+        assert_eq!(firefox.app_id.as_deref(), Some("firefox"));
+        assert!(firefox.is_fullscreen);
+        assert_eq!(
+            firefox.geometry,
+            Some(WindowGeometry {
+                position: Some((0.0, 0.0)),
+                size: (2560.0, 1440.0),
+            })
+        );
+
+        let web = &state.workspaces[&3];
+        assert_eq!(web.name.as_deref(), Some("web"));
+        assert!(web.is_active && !web.is_focused);
+        assert_eq!(web.active_window_id, Some(0x55d1c0b41230));
+        assert_eq!(state.workspaces[&1].name, None);
+
+        let laptop = &state.outputs["eDP-1"];
+        assert_eq!((laptop.width, laptop.height), (1440, 900));
+    }
+
+    #[test]
+    fn events_only_refresh_what_they_change() {
+        let mut snapshot = snapshot();
+
+        assert_eq!(
+            snapshot.apply_event("urgent", "55d1c0b41230"),
+            Refresh::NONE
+        );
+        assert!(snapshot.to_state().workspaces[&3].is_urgent);
+
+        let refresh = snapshot.apply_event("activewindowv2", "55d1c0b41230");
+        assert_eq!(
+            refresh,
+            Refresh {
+                workspaces: true,
+                ..Refresh::NONE
+            }
+        );
+        let state = snapshot.to_state();
+        assert_eq!(state.focused_window_id, Some(0x55d1c0b41230));
+        assert!(!state.windows[&0x55d1c0b41230].is_urgent);
+
+        assert_eq!(
+            snapshot.apply_event("windowtitlev2", "55d1c0a3e0f0,vim"),
+            Refresh {
+                clients: true,
+                ..Refresh::NONE
+            }
+        );
+        assert_eq!(
+            snapshot.apply_event("monitorremoved", "HDMI-A-1"),
+            Refresh::ALL
+        );
+        assert_eq!(
+            snapshot.apply_event("activelayout", "kbd,us"),
+            Refresh::NONE
+        );
+    }
+}
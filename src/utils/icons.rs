@@ -0,0 +1,93 @@
+use crate::utils::app_resolver::AppInfo;
+use gtk4::prelude::*;
+use gtk4::{gdk, gio, IconTheme};
+use std::{cell::RefCell, collections::HashMap, path::Path, sync::Once};
+
+pub const FALLBACK_APP_ICON: &str = "application-x-executable";
+
+/// `Icon=` should be a bare theme name, but plenty of entries carry an extension.
+const ICON_EXTENSIONS: [&str; 3] = [".png", ".svg", ".xpm"];
+
+static WATCH_THEME: Once = Once::new();
+
+thread_local! {
+    static CACHE: RefCell<HashMap<(Vec<String>, String), gio::Icon>> = RefCell::new(HashMap::new());
+}
+
+fn theme() -> Option<IconTheme> {
+    gdk::Display::default().map(|display| IconTheme::for_display(&display))
+}
+
+fn file_icon(path: &Path) -> Option<gio::Icon> {
+    path.is_file()
+        .then(|| gio::FileIcon::new(&gio::File::for_path(path)).upcast())
+}
+
+fn lookup_one(candidate: &str, theme: Option<&IconTheme>) -> Option<gio::Icon> {
+    if candidate.is_empty() {
+        return None;
+    }
+    if candidate.starts_with("file://") {
+        return gio::File::for_uri(candidate)
+            .path()
+            .and_then(|path| file_icon(&path));
+    }
+    if candidate.starts_with('/') {
+        return file_icon(Path::new(candidate));
+    }
+
+    let lowercase = candidate.to_lowercase();
+    let stem = ICON_EXTENSIONS
+        .iter()
+        .find_map(|ext| candidate.strip_suffix(ext));
+    let mut names = vec![candidate, lowercase.as_str()];
+    names.extend(stem);
+
+    let name = match theme {
+        Some(theme) => names.into_iter().find(|name| theme.has_icon(name))?,
+        None => candidate,
+    };
+    Some(gio::ThemedIcon::new(name).upcast())
+}
+
+/// Returns the first candidate that exists as a file or in the current icon
+/// theme, trying lowercase and extension-less variants, else `fallback`.
+/// Results are cached until the icon theme changes.
+pub fn lookup(candidates: &[&str], fallback: &str) -> gio::Icon {
+    let theme = theme();
+    WATCH_THEME.call_once(|| {
+        if let Some(theme) = &theme {
+            theme.connect_changed(|_| CACHE.with(|cache| cache.borrow_mut().clear()));
+        }
+    });
+
+    let key = (
+        candidates.iter().map(|c| c.to_string()).collect(),
+        fallback.to_string(),
+    );
+    if let Some(icon) = CACHE.with(|cache| cache.borrow().get(&key).cloned()) {
+        return icon;
+    }
+
+    let icon = candidates
+        .iter()
+        .find_map(|candidate| lookup_one(candidate, theme.as_ref()))
+        .unwrap_or_else(|| gio::ThemedIcon::new(fallback).upcast());
+    CACHE.with(|cache| cache.borrow_mut().insert(key, icon.clone()));
+    icon
+}
+
+/// Icon for an application, falling back through its desktop id and the
+/// compositor app id.
+pub fn app_icon(app: Option<&AppInfo>, app_id: &str) -> gio::Icon {
+    let mut candidates = Vec::new();
+    if let Some(app) = app {
+        // The resolver fills in the generic icon when `Icon=` is missing.
+        if app.icon != FALLBACK_APP_ICON {
+            candidates.push(app.icon.as_str());
+        }
+        candidates.push(app.desktop_id.as_str());
+    }
+    candidates.push(app_id);
+    lookup(&candidates, FALLBACK_APP_ICON)
+}
@@ -0,0 +1,114 @@
+use crate::utils::compositor;
+use gtk4::prelude::*;
+use gtk4::{gdk, gio, glib};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Once, OnceLock},
+    time::{Duration, Instant},
+};
+use tokio::sync::watch;
+
+const LAUNCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+static LAUNCHES: OnceLock<watch::Sender<PendingLaunches>> = OnceLock::new();
+static WATCHING: Once = Once::new();
+
+pub type PendingLaunches = HashMap<String, PendingLaunch>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LaunchOrigin {
+    Dock,
+    AppMenu,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingLaunch {
+    pub app_ids: Vec<String>,
+    pub origin: LaunchOrigin,
+    known_windows: HashSet<u64>,
+    started: Instant,
+}
+
+fn sender() -> &'static watch::Sender<PendingLaunches> {
+    LAUNCHES.get_or_init(|| watch::channel(PendingLaunches::new()).0)
+}
+
+fn matching_windows(state: &compositor::CompositorState, app_ids: &[String]) -> HashSet<u64> {
+    state
+        .windows
+        .values()
+        .filter(|w| {
+            w.app_id
+                .as_ref()
+                .is_some_and(|id| app_ids.contains(&id.to_lowercase()))
+        })
+        .map(|w| w.id)
+        .collect()
+}
+
+fn watch_windows() {
+    let mut state_rx = compositor::compositor().subscribe();
+    glib::MainContext::default().spawn_local(async move {
+        while state_rx.changed().await.is_ok() {
+            let state = state_rx.borrow_and_update();
+            sender().send_if_modified(|launches| {
+                let before = launches.len();
+                launches.retain(|_, launch| {
+                    matching_windows(&state, &launch.app_ids).is_subset(&launch.known_windows)
+                });
+                launches.len() != before
+            });
+        }
+    });
+}
+
+pub fn subscribe() -> watch::Receiver<PendingLaunches> {
+    sender().subscribe()
+}
+
+/// Marks `app_ids` as launching until one of them opens a new window or the timeout fires.
+/// `app_ids` must be lowercase; the first one keys the launch.
+pub fn track(app_ids: Vec<String>, origin: LaunchOrigin) {
+    let Some(key) = app_ids.first().cloned() else {
+        return;
+    };
+    WATCHING.call_once(watch_windows);
+
+    let known_windows = matching_windows(&compositor::compositor().subscribe().borrow(), &app_ids);
+    let started = Instant::now();
+    sender().send_modify(|launches| {
+        launches.insert(
+            key.clone(),
+            PendingLaunch {
+                app_ids,
+                origin,
+                known_windows,
+                started,
+            },
+        );
+    });
+
+    glib::timeout_add_local_once(LAUNCH_TIMEOUT, move || {
+        sender().send_if_modified(|launches| {
+            let expired = launches.get(&key).is_some_and(|l| l.started == started);
+            if expired {
+                launches.remove(&key);
+            }
+            expired
+        });
+    });
+}
+
+pub fn is_launching(launches: &PendingLaunches, app_ids: &[String]) -> bool {
+    launches
+        .values()
+        .any(|launch| launch.app_ids.iter().any(|id| app_ids.contains(id)))
+}
+
+/// Requests an XDG activation token so the launched app may take focus.
+pub fn activation_token() -> Option<String> {
+    let context = gdk::Display::default()?.app_launch_context();
+    context
+        .startup_notify_id(None::<&gio::AppInfo>, &[])
+        .map(|token| token.to_string())
+}
@@ -0,0 +1,581 @@
+use crate::utils::{
+    app_resolver::{AppInfo, DesktopAction},
+    launch_feedback,
+};
+use gtk4::glib;
+use std::{
+    collections::HashMap,
+    env,
+    error::Error,
+    fmt, io,
+    os::unix::process::CommandExt,
+    path::Path,
+    process::{Command, Stdio},
+};
+use tokio::sync::OnceCell;
+use zbus::{
+    fdo::DBusProxy,
+    names::BusName,
+    zvariant::{OwnedObjectPath, Value},
+    Connection, Proxy,
+};
+
+const APPLICATION_INTERFACE: &str = "org.freedesktop.Application";
+const SYSTEMD_SERVICE: &str = "org.freedesktop.systemd1";
+/// Errors systemd answers with when the process exited before it could be moved.
+const PROCESS_GONE_ERRORS: &[&str] = &[
+    "org.freedesktop.DBus.Error.UnixProcessIdUnknown",
+    "System.Error.ESRCH",
+];
+
+static SESSION_BUS: OnceCell<Connection> = OnceCell::const_new();
+static SYSTEMD_MANAGER: OnceCell<Option<Proxy<'static>>> = OnceCell::const_new();
+
+/// Terminal emulators tried for `Terminal=true` entries when `$TERMINAL` is unset,
+/// with the arguments that precede the command. A bare `$TERMINAL` naming one of
+/// these gets the same arguments.
+const TERMINALS: &[(&str, &[&str])] = &[
+    ("xdg-terminal-exec", &[]),
+    ("kitty", &[]),
+    ("foot", &[]),
+    ("ghostty", &["-e"]),
+    ("alacritty", &["-e"]),
+    ("wezterm", &["start", "--"]),
+    ("gnome-terminal", &["--"]),
+    ("konsole", &["-e"]),
+    ("xterm", &["-e"]),
+    ("urxvt", &["-e"]),
+    ("st", &["-e"]),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecError {
+    Empty,
+    UnterminatedQuote,
+    TrailingPercent,
+    InvalidFieldCode(char),
+}
+
+impl fmt::Display for ExecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecError::Empty => write!(f, "Exec key is empty"),
+            ExecError::UnterminatedQuote => write!(f, "Exec key has an unterminated quote"),
+            ExecError::TrailingPercent => write!(f, "Exec key ends with a lone '%'"),
+            ExecError::InvalidFieldCode(code) => write!(f, "Invalid field code %{}", code),
+        }
+    }
+}
+
+impl Error for ExecError {}
+
+#[derive(Debug)]
+pub enum LaunchError {
+    Exec(ExecError),
+    NoTerminal,
+    Spawn(io::Error),
+}
+
+impl fmt::Display for LaunchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LaunchError::Exec(e) => write!(f, "{}", e),
+            LaunchError::NoTerminal => write!(f, "No terminal emulator found"),
+            LaunchError::Spawn(e) => write!(f, "Failed to spawn process: {}", e),
+        }
+    }
+}
+
+impl Error for LaunchError {}
+
+impl From<ExecError> for LaunchError {
+    fn from(err: ExecError) -> Self {
+        LaunchError::Exec(err)
+    }
+}
+
+impl From<io::Error> for LaunchError {
+    fn from(err: io::Error) -> Self {
+        LaunchError::Spawn(err)
+    }
+}
+
+/// Values substituted for the `%i`, `%c` and `%k` field codes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExecContext<'a> {
+    pub name: &'a str,
+    pub icon: Option<&'a str>,
+    pub desktop_file: Option<&'a Path>,
+}
+
+impl<'a> From<&'a AppInfo> for ExecContext<'a> {
+    fn from(app: &'a AppInfo) -> Self {
+        Self {
+            name: &app.name,
+            icon: Some(app.icon.as_str()).filter(|icon| !icon.is_empty()),
+            desktop_file: Some(app.path.as_path()),
+        }
+    }
+}
+
+/// Splits an Exec value into arguments following the desktop entry quoting rules.
+/// String-level escapes (`\s`, `\\`, ...) must already have been resolved.
+pub fn split_exec(exec: &str) -> Result<Vec<String>, ExecError> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped @ ('"' | '`' | '$' | '\\')) => current.push(escaped),
+                            Some(other) => {
+                                current.push('\\');
+                                current.push(other);
+                            }
+                            None => return Err(ExecError::UnterminatedQuote),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err(ExecError::UnterminatedQuote),
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                in_arg = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+
+    if args.is_empty() {
+        Err(ExecError::Empty)
+    } else {
+        Ok(args)
+    }
+}
+
+/// Splits an Exec value and expands its field codes. No files or URLs are passed,
+/// so `%f`, `%F`, `%u` and `%U` expand to nothing.
+pub fn expand_exec(exec: &str, context: &ExecContext) -> Result<Vec<String>, ExecError> {
+    let mut argv = Vec::new();
+    for arg in split_exec(exec)? {
+        match arg.as_str() {
+            "%f" | "%F" | "%u" | "%U" | "%d" | "%D" | "%n" | "%N" | "%v" | "%m" => {}
+            "%i" => {
+                if let Some(icon) = context.icon {
+                    argv.push("--icon".to_string());
+                    argv.push(icon.to_string());
+                }
+            }
+            _ => argv.push(expand_field_codes(&arg, context)?),
+        }
+    }
+
+    if argv.is_empty() {
+        Err(ExecError::Empty)
+    } else {
+        Ok(argv)
+    }
+}
+
+fn expand_field_codes(arg: &str, context: &ExecContext) -> Result<String, ExecError> {
+    let mut expanded = String::with_capacity(arg.len());
+    let mut chars = arg.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => expanded.push('%'),
+            Some('c') => expanded.push_str(context.name),
+            Some('k') => {
+                if let Some(path) = context.desktop_file {
+                    expanded.push_str(&path.to_string_lossy());
+                }
+            }
+            Some('f' | 'F' | 'u' | 'U' | 'i' | 'd' | 'D' | 'n' | 'N' | 'v' | 'm') => {}
+            Some(code) => return Err(ExecError::InvalidFieldCode(code)),
+            None => return Err(ExecError::TrailingPercent),
+        }
+    }
+    Ok(expanded)
+}
+
+/// Launches a desktop entry, or one of its actions, detached from kaneru.
+pub fn launch(app: &AppInfo, action: Option<&DesktopAction>) -> Result<(), LaunchError> {
+    let token = launch_feedback::activation_token();
+
+    if app.dbus_activatable {
+        let app = app.clone();
+        let action = action.cloned();
+        tokio::spawn(async move {
+            let action_id = action.as_ref().map(|a| a.id.as_str());
+            if let Err(e) = dbus_activate(&app.desktop_id, action_id, token.as_deref()).await {
+                eprintln!(
+                    "D-Bus activation of {} failed: {}. Falling back to Exec.",
+                    app.desktop_id, e
+                );
+                if let Err(e) = spawn_entry(&app, action.as_ref(), token.as_deref()) {
+                    eprintln!("Failed to launch {}: {}", app.desktop_id, e);
+                }
+            }
+        });
+        return Ok(());
+    }
+
+    spawn_entry(app, action, token.as_deref())
+}
+
+/// Runs a plain command line, for apps without a desktop entry.
+pub fn spawn(argv: &[String]) -> Result<(), LaunchError> {
+    let token = launch_feedback::activation_token();
+    spawn_detached(argv, None, token.as_deref(), None)
+}
+
+fn spawn_entry(
+    app: &AppInfo,
+    action: Option<&DesktopAction>,
+    token: Option<&str>,
+) -> Result<(), LaunchError> {
+    let exec = action.map_or(app.exec.as_str(), |a| a.exec.as_str());
+    let mut argv = expand_exec(exec, &ExecContext::from(app))?;
+    if app.terminal {
+        let mut terminal = terminal_command().ok_or(LaunchError::NoTerminal)?;
+        terminal.append(&mut argv);
+        argv = terminal;
+    }
+    spawn_detached(
+        &argv,
+        app.working_dir.as_deref(),
+        token,
+        Some(&app.desktop_id),
+    )
+}
+
+fn spawn_detached(
+    argv: &[String],
+    working_dir: Option<&Path>,
+    token: Option<&str>,
+    app_id: Option<&str>,
+) -> Result<(), LaunchError> {
+    let (program, args) = argv.split_first().ok_or(ExecError::Empty)?;
+    let mut cmd = Command::new(program);
+    cmd.args(args)
+        .stdin(Stdio::null())
+        // A process group of its own keeps the app alive when kaneru's group is signalled.
+        .process_group(0);
+    if let Some(dir) = working_dir.filter(|dir| dir.is_dir()) {
+        cmd.current_dir(dir);
+    }
+    if let Some(token) = token {
+        cmd.env("XDG_ACTIVATION_TOKEN", token);
+        cmd.env("DESKTOP_STARTUP_ID", token);
+    }
+
+    let pid = cmd.spawn()?.id();
+    // GLib reaps the child from its main loop, so exited apps don't linger as zombies.
+    glib::child_watch_add(glib::Pid(pid as i32), |_, _| {});
+
+    let unit = scope_name(app_id.unwrap_or(program), pid);
+    tokio::spawn(async move {
+        match move_to_scope(&unit, pid).await {
+            Ok(()) => {}
+            Err(zbus::Error::MethodError(name, _, _))
+                if PROCESS_GONE_ERRORS.contains(&name.as_str()) => {}
+            Err(e) => eprintln!("Could not move pid {} into {}: {}", pid, unit, e),
+        }
+    });
+    Ok(())
+}
+
+fn scope_name(app_id: &str, pid: u32) -> String {
+    let app_id: String = Path::new(app_id)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("app-kaneru-{}-{}.scope", app_id, pid)
+}
+
+/// The session bus connection shared by every launch.
+async fn session_bus() -> zbus::Result<&'static Connection> {
+    SESSION_BUS.get_or_try_init(Connection::session).await
+}
+
+/// The systemd user manager, or `None` when systemd isn't managing the
+/// session. Checked once, on the first launch.
+async fn systemd_manager() -> Option<&'static Proxy<'static>> {
+    SYSTEMD_MANAGER
+        .get_or_init(|| async {
+            let conn = session_bus().await.ok()?;
+            let name = BusName::try_from(SYSTEMD_SERVICE).ok()?;
+            let running = DBusProxy::new(conn).await.ok()?.name_has_owner(name).await;
+            if !running.unwrap_or(false) {
+                return None;
+            }
+            Proxy::new(
+                conn,
+                SYSTEMD_SERVICE,
+                "/org/freedesktop/systemd1",
+                "org.freedesktop.systemd1.Manager",
+            )
+            .await
+            .ok()
+        })
+        .await
+        .as_ref()
+}
+
+/// Moves a launched process into a transient systemd user scope, so it is tracked
+/// as its own app rather than as part of kaneru's service. Does nothing without
+/// systemd.
+async fn move_to_scope(unit: &str, pid: u32) -> zbus::Result<()> {
+    let Some(proxy) = systemd_manager().await else {
+        return Ok(());
+    };
+    let properties: Vec<(&str, Value)> = vec![
+        ("PIDs", Value::from(vec![pid])),
+        ("CollectMode", Value::from("inactive-or-failed")),
+    ];
+    let aux: Vec<(&str, Vec<(&str, Value)>)> = Vec::new();
+    let _job: OwnedObjectPath = proxy
+        .call("StartTransientUnit", &(unit, "fail", properties, aux))
+        .await?;
+    Ok(())
+}
+
+async fn dbus_activate(
+    desktop_id: &str,
+    action: Option<&str>,
+    token: Option<&str>,
+) -> zbus::Result<()> {
+    let conn = session_bus().await?;
+    let path = format!("/{}", desktop_id.replace('.', "/").replace('-', "_"));
+    let proxy = Proxy::new(conn, desktop_id, path.as_str(), APPLICATION_INTERFACE).await?;
+
+    let mut platform_data: HashMap<&str, Value> = HashMap::new();
+    if let Some(token) = token {
+        platform_data.insert("activation-token", Value::from(token));
+        platform_data.insert("desktop-startup-id", Value::from(token));
+    }
+
+    match action {
+        Some(action) => {
+            let parameters: Vec<Value> = Vec::new();
+            proxy
+                .call::<_, _, ()>("ActivateAction", &(action, parameters, platform_data))
+                .await
+        }
+        None => proxy.call::<_, _, ()>("Activate", &(platform_data,)).await,
+    }
+}
+
+fn in_path(program: &str) -> bool {
+    env::var_os("PATH")
+        .is_some_and(|path| env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+fn terminal_command() -> Option<Vec<String>> {
+    if let Some(terminal) = env::var("TERMINAL")
+        .ok()
+        .and_then(|terminal| split_exec(&terminal).ok())
+    {
+        return Some(with_terminal_args(terminal));
+    }
+
+    TERMINALS
+        .iter()
+        .find(|(program, _)| in_path(program))
+        .map(|(program, args)| {
+            std::iter::once(program)
+                .chain(args.iter())
+                .map(|arg| arg.to_string())
+                .collect()
+        })
+}
+
+/// `$TERMINAL` may hold just the program or a full command line. A bare program
+/// gets the arguments known for it; anything else is used as given, since
+/// terminals disagree on whether the command needs `-e` in front.
+fn with_terminal_args(mut terminal: Vec<String>) -> Vec<String> {
+    if let [program] = terminal.as_slice() {
+        let name = Path::new(program)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if let Some((_, args)) = TERMINALS.iter().find(|(known, _)| *known == name) {
+            terminal.extend(args.iter().map(|arg| arg.to_string()));
+        }
+    }
+    terminal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> ExecContext<'static> {
+        ExecContext {
+            name: "Text Editor",
+            icon: Some("accessories-text-editor"),
+            desktop_file: Some(Path::new("/usr/share/applications/editor.desktop")),
+        }
+    }
+
+    #[test]
+    fn splits_on_whitespace() {
+        assert_eq!(
+            split_exec("  firefox   --new-window ").unwrap(),
+            vec!["firefox", "--new-window"]
+        );
+    }
+
+    #[test]
+    fn quoted_arguments_keep_spaces_and_escapes() {
+        assert_eq!(
+            split_exec(r#"sh -c "echo \"a b\" \$HOME \\ \`x\`""#).unwrap(),
+            vec!["sh", "-c", r#"echo "a b" $HOME \ `x`"#]
+        );
+        assert_eq!(
+            split_exec(r#""/opt/My App/app" --flag"#).unwrap(),
+            vec!["/opt/My App/app", "--flag"]
+        );
+        assert_eq!(split_exec(r#"app """#).unwrap(), vec!["app", ""]);
+    }
+
+    #[test]
+    fn unknown_escapes_inside_quotes_are_kept() {
+        assert_eq!(split_exec(r#"app "a\nb""#).unwrap(), vec!["app", r"a\nb"]);
+    }
+
+    #[test]
+    fn unterminated_quote_is_an_error() {
+        assert_eq!(
+            split_exec(r#"app "unterminated"#),
+            Err(ExecError::UnterminatedQuote)
+        );
+        assert_eq!(
+            split_exec(r#"app "trailing\"#),
+            Err(ExecError::UnterminatedQuote)
+        );
+    }
+
+    #[test]
+    fn empty_exec_is_an_error() {
+        assert_eq!(split_exec("   "), Err(ExecError::Empty));
+        assert_eq!(expand_exec("%U", &context()), Err(ExecError::Empty));
+    }
+
+    #[test]
+    fn file_and_url_codes_are_dropped() {
+        assert_eq!(
+            expand_exec("gimp %U --no-splash %f", &context()).unwrap(),
+            vec!["gimp", "--no-splash"]
+        );
+        assert_eq!(
+            expand_exec("app --open=%u", &context()).unwrap(),
+            vec!["app", "--open="]
+        );
+    }
+
+    #[test]
+    fn icon_name_and_location_codes_expand() {
+        assert_eq!(
+            expand_exec("editor %i --title=%c %k", &context()).unwrap(),
+            vec![
+                "editor",
+                "--icon",
+                "accessories-text-editor",
+                "--title=Text Editor",
+                "/usr/share/applications/editor.desktop",
+            ]
+        );
+
+        let no_icon = ExecContext {
+            icon: None,
+            ..context()
+        };
+        assert_eq!(expand_exec("editor %i", &no_icon).unwrap(), vec!["editor"]);
+    }
+
+    #[test]
+    fn double_percent_is_a_literal() {
+        assert_eq!(
+            expand_exec("date +%%H:%%M", &context()).unwrap(),
+            vec!["date", "+%H:%M"]
+        );
+    }
+
+    #[test]
+    fn invalid_field_codes_are_rejected() {
+        assert_eq!(
+            expand_exec("app %x", &context()),
+            Err(ExecError::InvalidFieldCode('x'))
+        );
+        assert_eq!(
+            expand_exec("app 100%", &context()),
+            Err(ExecError::TrailingPercent)
+        );
+    }
+
+    #[test]
+    fn scope_names_are_valid_unit_names() {
+        assert_eq!(
+            scope_name("org.gnome.Nautilus", 42),
+            "app-kaneru-org.gnome.Nautilus-42.scope"
+        );
+        assert_eq!(
+            scope_name("/usr/bin/my-app", 7),
+            "app-kaneru-my_app-7.scope"
+        );
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn terminal_args_are_only_added_where_known() {
+        assert_eq!(
+            with_terminal_args(args(&["alacritty"])),
+            args(&["alacritty", "-e"])
+        );
+        assert_eq!(
+            with_terminal_args(args(&["/usr/bin/wezterm"])),
+            args(&["/usr/bin/wezterm", "start", "--"])
+        );
+        assert_eq!(with_terminal_args(args(&["kitty"])), args(&["kitty"]));
+        assert_eq!(
+            with_terminal_args(args(&["my-terminal"])),
+            args(&["my-terminal"])
+        );
+        assert_eq!(
+            with_terminal_args(args(&["foot", "--app-id", "term"])),
+            args(&["foot", "--app-id", "term"])
+        );
+    }
+}
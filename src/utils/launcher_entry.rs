@@ -0,0 +1,371 @@
+use futures_util::stream::{StreamExt, TryStreamExt};
+use std::{
+    collections::HashMap,
+    sync::{Once, OnceLock},
+    time::Duration,
+};
+use tokio::sync::watch;
+use zbus::{
+    fdo::DBusProxy,
+    message::Type as MessageType,
+    zvariant::{OwnedValue, Value},
+    Connection, MatchRule, MessageStream,
+};
+
+const INTERFACE: &str = "com.canonical.Unity.LauncherEntry";
+const UPDATE_SIGNAL: &str = "Update";
+const APP_URI_PREFIX: &str = "application://";
+const RECONNECT_DELAY_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_DELAY_MAX: Duration = Duration::from_secs(30);
+
+static ENTRIES: OnceLock<watch::Sender<LauncherEntries>> = OnceLock::new();
+static STARTED: Once = Once::new();
+
+pub type LauncherEntries = HashMap<String, LauncherEntry>;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LauncherEntry {
+    pub count: i64,
+    pub count_visible: bool,
+    pub progress: f64,
+    pub progress_visible: bool,
+    pub urgent: bool,
+}
+
+impl LauncherEntry {
+    pub fn badge(&self) -> Option<i64> {
+        (self.count_visible && self.count > 0).then_some(self.count)
+    }
+
+    pub fn progress(&self) -> Option<f64> {
+        self.progress_visible
+            .then_some(self.progress.clamp(0.0, 1.0))
+    }
+
+    fn apply(&mut self, properties: &HashMap<String, OwnedValue>) {
+        for (key, value) in properties {
+            match (key.as_str(), &**value) {
+                ("count", value) => {
+                    if let Some(count) = as_i64(value) {
+                        self.count = count;
+                    }
+                }
+                ("count-visible", Value::Bool(visible)) => self.count_visible = *visible,
+                ("progress", Value::F64(progress)) => self.progress = *progress,
+                ("progress-visible", Value::Bool(visible)) => self.progress_visible = *visible,
+                ("urgent", Value::Bool(urgent)) => self.urgent = *urgent,
+                _ => {}
+            }
+        }
+    }
+}
+
+fn as_i64(value: &Value<'_>) -> Option<i64> {
+    match value {
+        Value::I64(n) => Some(*n),
+        Value::I32(n) => Some(*n as i64),
+        Value::U32(n) => Some(*n as i64),
+        Value::U64(n) => i64::try_from(*n).ok(),
+        _ => None,
+    }
+}
+
+fn desktop_id_from_uri(app_uri: &str) -> Option<String> {
+    let desktop_file = app_uri.strip_prefix(APP_URI_PREFIX).unwrap_or(app_uri);
+    let desktop_id = desktop_file
+        .strip_suffix(".desktop")
+        .unwrap_or(desktop_file);
+    if desktop_id.is_empty() {
+        None
+    } else {
+        Some(desktop_id.to_lowercase())
+    }
+}
+
+/// Applies an update and remembers `sender`, the unique bus name of the client that
+/// sent it, so its entries can be dropped once it leaves the bus.
+fn update(
+    entries: &mut LauncherEntries,
+    senders: &mut HashMap<String, String>,
+    sender: Option<&str>,
+    desktop_id: String,
+    properties: &HashMap<String, OwnedValue>,
+) -> bool {
+    match sender {
+        Some(sender) => senders.insert(desktop_id.clone(), sender.to_string()),
+        None => senders.remove(&desktop_id),
+    };
+    let entry = entries.entry(desktop_id).or_default();
+    let previous = entry.clone();
+    entry.apply(properties);
+    *entry != previous
+}
+
+/// Drops the entries of a client that left the bus. Apps that quit without
+/// resetting their badge or progress would otherwise keep showing it.
+fn forget(
+    entries: &mut LauncherEntries,
+    senders: &mut HashMap<String, String>,
+    gone: &str,
+) -> bool {
+    let before = entries.len();
+    senders.retain(|desktop_id, sender| {
+        let keep = sender != gone;
+        if !keep {
+            entries.remove(desktop_id);
+        }
+        keep
+    });
+    entries.len() != before
+}
+
+async fn listen(tx: &watch::Sender<LauncherEntries>, connected: &mut bool) -> zbus::Result<()> {
+    let conn = Connection::session().await?;
+    let rule = MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .interface(INTERFACE)?
+        .member(UPDATE_SIGNAL)?
+        .build();
+    let mut stream = MessageStream::for_match_rule(rule, &conn, None).await?;
+    let mut owner_changes = DBusProxy::new(&conn)
+        .await?
+        .receive_name_owner_changed()
+        .await?;
+    *connected = true;
+
+    let mut senders = HashMap::new();
+    loop {
+        tokio::select! {
+            msg = stream.try_next() => {
+                let Some(msg) = msg? else {
+                    return Ok(());
+                };
+                let Ok((app_uri, properties)) = msg
+                    .body()
+                    .deserialize::<(String, HashMap<String, OwnedValue>)>()
+                else {
+                    continue;
+                };
+                let Some(desktop_id) = desktop_id_from_uri(&app_uri) else {
+                    continue;
+                };
+                let header = msg.header();
+                let sender = header.sender().map(|sender| sender.as_str());
+                tx.send_if_modified(|entries| {
+                    update(entries, &mut senders, sender, desktop_id, &properties)
+                });
+            }
+            change = owner_changes.next() => {
+                let Some(change) = change else {
+                    return Ok(());
+                };
+                let Ok(args) = change.args() else {
+                    continue;
+                };
+                if args.new_owner().is_none() {
+                    let gone = args.name().as_str();
+                    tx.send_if_modified(|entries| forget(entries, &mut senders, gone));
+                }
+            }
+        }
+    }
+}
+
+pub fn subscribe() -> watch::Receiver<LauncherEntries> {
+    let tx = ENTRIES.get_or_init(|| watch::channel(LauncherEntries::new()).0);
+    STARTED.call_once(|| {
+        tokio::spawn(async move {
+            let mut delay = RECONNECT_DELAY_MIN;
+            loop {
+                let mut connected = false;
+                match listen(tx, &mut connected).await {
+                    Ok(()) => eprintln!("LauncherEntry listener closed, reconnecting..."),
+                    Err(e) => eprintln!("LauncherEntry listener error: {}", e),
+                }
+                // Senders are unknown after reconnecting, so their entries could never be
+                // dropped.
+                tx.send_if_modified(|entries| {
+                    let had_entries = !entries.is_empty();
+                    entries.clear();
+                    had_entries
+                });
+                if connected {
+                    delay = RECONNECT_DELAY_MIN;
+                }
+
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(RECONNECT_DELAY_MAX);
+            }
+        });
+    });
+    tx.subscribe()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn properties(values: Vec<(&str, Value<'static>)>) -> HashMap<String, OwnedValue> {
+        values
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), OwnedValue::try_from(value).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn desktop_ids_come_from_application_uris() {
+        assert_eq!(
+            desktop_id_from_uri("application://org.gnome.Nautilus.desktop").as_deref(),
+            Some("org.gnome.nautilus")
+        );
+        assert_eq!(
+            desktop_id_from_uri("firefox.desktop").as_deref(),
+            Some("firefox")
+        );
+        assert_eq!(
+            desktop_id_from_uri("application://foot").as_deref(),
+            Some("foot")
+        );
+        assert_eq!(desktop_id_from_uri("application://.desktop"), None);
+        assert_eq!(desktop_id_from_uri(""), None);
+    }
+
+    #[test]
+    fn updates_only_change_the_properties_they_carry() {
+        let mut entry = LauncherEntry::default();
+        entry.apply(&properties(vec![
+            ("count", Value::I64(3)),
+            ("count-visible", Value::Bool(true)),
+            ("progress", Value::F64(0.25)),
+            ("progress-visible", Value::Bool(true)),
+            ("urgent", Value::Bool(true)),
+        ]));
+        assert_eq!(
+            entry,
+            LauncherEntry {
+                count: 3,
+                count_visible: true,
+                progress: 0.25,
+                progress_visible: true,
+                urgent: true,
+            }
+        );
+
+        entry.apply(&properties(vec![
+            ("progress-visible", Value::Bool(false)),
+            ("urgent", Value::Bool(false)),
+        ]));
+        assert_eq!(entry.badge(), Some(3));
+        assert_eq!(entry.progress(), None);
+        assert!(!entry.urgent);
+    }
+
+    #[test]
+    fn counts_accept_any_integer_type() {
+        let mut entry = LauncherEntry::default();
+        entry.apply(&properties(vec![("count", Value::I32(7))]));
+        assert_eq!(entry.count, 7);
+        entry.apply(&properties(vec![("count", Value::U32(8))]));
+        assert_eq!(entry.count, 8);
+        entry.apply(&properties(vec![("count", Value::U64(9))]));
+        assert_eq!(entry.count, 9);
+
+        // Out of range or mistyped values leave the last good count.
+        entry.apply(&properties(vec![("count", Value::U64(u64::MAX))]));
+        entry.apply(&properties(vec![("count", Value::from("10"))]));
+        assert_eq!(entry.count, 9);
+    }
+
+    #[test]
+    fn mistyped_and_unknown_properties_are_ignored() {
+        let mut entry = LauncherEntry::default();
+        entry.apply(&properties(vec![
+            ("count-visible", Value::from("true")),
+            ("progress", Value::I64(1)),
+            ("urgent", Value::U32(1)),
+            ("quicklist", Value::from("/com/example/menu")),
+        ]));
+        assert_eq!(entry, LauncherEntry::default());
+    }
+
+    #[test]
+    fn badge_and_progress_respect_visibility_and_range() {
+        let mut entry = LauncherEntry {
+            count: 4,
+            count_visible: false,
+            progress: 1.5,
+            progress_visible: true,
+            urgent: false,
+        };
+        assert_eq!(entry.badge(), None);
+        assert_eq!(entry.progress(), Some(1.0));
+
+        entry.count_visible = true;
+        entry.progress = -0.5;
+        assert_eq!(entry.badge(), Some(4));
+        assert_eq!(entry.progress(), Some(0.0));
+
+        entry.count = 0;
+        assert_eq!(entry.badge(), None);
+    }
+
+    #[test]
+    fn entries_are_dropped_when_their_sender_leaves() {
+        let mut entries = LauncherEntries::new();
+        let mut senders = HashMap::new();
+        let count = properties(vec![
+            ("count", Value::I64(3)),
+            ("count-visible", Value::Bool(true)),
+        ]);
+        for (sender, desktop_id) in [
+            (":1.10", "firefox"),
+            (":1.10", "thunderbird"),
+            (":1.20", "foot"),
+        ] {
+            assert!(update(
+                &mut entries,
+                &mut senders,
+                Some(sender),
+                desktop_id.to_string(),
+                &count
+            ));
+        }
+        assert!(!update(
+            &mut entries,
+            &mut senders,
+            Some(":1.10"),
+            "firefox".to_string(),
+            &count
+        ));
+
+        assert!(!forget(&mut entries, &mut senders, ":1.30"));
+        assert!(forget(&mut entries, &mut senders, ":1.10"));
+        assert_eq!(entries.keys().collect::<Vec<_>>(), vec!["foot"]);
+        assert!(!forget(&mut entries, &mut senders, ":1.10"));
+    }
+
+    #[test]
+    fn entries_follow_their_latest_sender() {
+        let mut entries = LauncherEntries::new();
+        let mut senders = HashMap::new();
+        let urgent = properties(vec![("urgent", Value::Bool(true))]);
+        update(
+            &mut entries,
+            &mut senders,
+            Some(":1.10"),
+            "firefox".to_string(),
+            &urgent,
+        );
+        update(
+            &mut entries,
+            &mut senders,
+            Some(":1.11"),
+            "firefox".to_string(),
+            &urgent,
+        );
+
+        assert!(!forget(&mut entries, &mut senders, ":1.10"));
+        assert!(forget(&mut entries, &mut senders, ":1.11"));
+        assert!(entries.is_empty());
+    }
+}
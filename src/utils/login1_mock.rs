@@ -0,0 +1,117 @@
+use std::{
+    collections::HashMap,
+    os::unix::net::UnixStream,
+    sync::{Arc, Mutex},
+};
+use zbus::{connection, interface, Connection, Guid};
+
+/// Stands in for `org.freedesktop.login1.Manager`: `Can*` methods answer
+/// from a table and every action is recorded instead of performed.
+struct Manager {
+    answers: HashMap<&'static str, &'static str>,
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+impl Manager {
+    fn answer(&self, method: &str) -> String {
+        self.answers
+            .get(method)
+            .copied()
+            .unwrap_or("na")
+            .to_string()
+    }
+
+    fn record(&self, call: String) {
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+#[interface(name = "org.freedesktop.login1.Manager")]
+impl Manager {
+    fn can_suspend(&self) -> String {
+        self.answer("CanSuspend")
+    }
+
+    fn can_hibernate(&self) -> String {
+        self.answer("CanHibernate")
+    }
+
+    fn can_reboot(&self) -> String {
+        self.answer("CanReboot")
+    }
+
+    fn can_power_off(&self) -> String {
+        self.answer("CanPowerOff")
+    }
+
+    fn suspend(&self, interactive: bool) {
+        self.record(format!("Suspend({})", interactive));
+    }
+
+    fn hibernate(&self, interactive: bool) {
+        self.record(format!("Hibernate({})", interactive));
+    }
+
+    fn reboot(&self, interactive: bool) {
+        self.record(format!("Reboot({})", interactive));
+    }
+
+    fn power_off(&self, interactive: bool) {
+        self.record(format!("PowerOff({})", interactive));
+    }
+
+    fn lock_session(&self, session_id: &str) {
+        self.record(format!("LockSession({})", session_id));
+    }
+
+    fn terminate_session(&self, session_id: &str) {
+        self.record(format!("TerminateSession({})", session_id));
+    }
+}
+
+/// A mock login1 served over a private peer-to-peer connection, so tests
+/// never touch the real system bus.
+pub struct MockLogin1 {
+    _service: Connection,
+    client: Connection,
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockLogin1 {
+    pub async fn start<const N: usize>(answers: [(&'static str, &'static str); N]) -> Self {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let manager = Manager {
+            answers: answers.into_iter().collect(),
+            calls: calls.clone(),
+        };
+
+        let (service_stream, client_stream) =
+            UnixStream::pair().expect("Failed to create mock login1 socket pair");
+        let service = connection::Builder::unix_stream(service_stream)
+            .server(Guid::generate())
+            .unwrap()
+            .p2p()
+            .serve_at("/org/freedesktop/login1", manager)
+            .unwrap()
+            .build();
+        let client = connection::Builder::unix_stream(client_stream)
+            .p2p()
+            .build();
+        let (service, client) =
+            tokio::try_join!(service, client).expect("Failed to connect to mock login1");
+
+        Self {
+            _service: service,
+            client,
+            calls,
+        }
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.client
+    }
+
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+}
@@ -1,17 +1,37 @@
+pub mod app_category;
+pub mod app_index;
 pub mod app_resolver;
+pub mod app_usage;
 pub mod battery;
+pub mod calculator;
+pub mod compositor;
 pub(crate) mod config;
 pub mod display_control;
 mod distro;
+pub mod dock_favorites;
+pub mod fuzzy;
+pub mod hyprland;
+pub mod icons;
+pub mod launch_feedback;
+pub mod launcher;
+pub mod launcher_entry;
+#[cfg(test)]
+mod login1_mock;
 pub mod network;
 pub mod niri;
+#[cfg(test)]
+mod niri_mock;
 pub(crate) mod notification;
 pub mod notification_manager;
 pub mod notification_server;
 mod persistence;
+pub mod search;
+pub mod session;
 mod style;
+pub mod sway;
 
 pub use app_resolver::AppResolver;
+pub use compositor::{compositor, init_compositor};
 pub use config::{load_config, BarConfig, NotificationPosition};
 pub use distro::get_distro_icon_name;
 pub use notification::{Notification, Urgency};
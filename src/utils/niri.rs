@@ -1,15 +1,23 @@
+use crate::utils::compositor::{
+    Compositor, CompositorError, CompositorState, OutputInfo, StateStream, WindowGeometry,
+    WindowInfo, WorkspaceInfo,
+};
 use niri_ipc::socket::SOCKET_PATH_ENV;
-use niri_ipc::{self, Reply, Request, Response};
+use niri_ipc::{self, Action, Reply, Request, Response, WorkspaceReferenceArg};
 use std::{
+    collections::HashMap,
     env,
     error::Error,
     fmt,
     io::{self, BufRead, BufReader, BufWriter, Write},
     os::unix::net::UnixStream,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
+use tokio::sync::watch;
+
+pub use niri_ipc::{Event, Output, Window, WindowLayout, Workspace};
 
-pub use niri_ipc::Window;
+static STATE_STREAM: StateStream = StateStream::new("niri");
 
 #[derive(Debug)]
 pub enum NiriError {
@@ -58,32 +66,25 @@ impl Error for NiriError {
     }
 }
 
-fn send_request<T>(
-    request: Request,
-    expected_response_fn: fn(Response) -> Option<T>,
-    expected_name: &'static str,
-) -> Result<T, NiriError> {
-    let socket_path = env::var(SOCKET_PATH_ENV)
+fn socket_path() -> Result<PathBuf, NiriError> {
+    env::var(SOCKET_PATH_ENV)
         .map(PathBuf::from)
-        .map_err(|_| NiriError::SocketPathNotSet)?;
-
-    let stream = UnixStream::connect(&socket_path).map_err(NiriError::Connection)?;
-    let reader_stream = stream.try_clone().map_err(NiriError::Connection)?;
-    let writer_stream = stream;
+        .map_err(|_| NiriError::SocketPathNotSet)
+}
 
-    let mut writer = BufWriter::new(writer_stream);
-    let mut reader = BufReader::new(reader_stream);
+fn write_request(stream: &UnixStream, request: &Request) -> Result<(), NiriError> {
+    let mut writer = BufWriter::new(stream);
 
-    let request_json = serde_json::to_string(&request).map_err(NiriError::Serialization)?;
+    let request_json = serde_json::to_string(request).map_err(NiriError::Serialization)?;
 
     writer
         .write_all(request_json.as_bytes())
         .and_then(|_| writer.write_all(b"\n"))
         .and_then(|_| writer.flush())
-        .map_err(NiriError::IPC)?;
-
-    drop(writer);
+        .map_err(NiriError::IPC)
+}
 
+fn read_reply(reader: &mut BufReader<UnixStream>) -> Result<Response, NiriError> {
     let mut reply_json = String::new();
     reader.read_line(&mut reply_json).map_err(NiriError::IPC)?;
 
@@ -95,24 +96,39 @@ fn send_request<T>(
     }
 
     let reply: Reply = serde_json::from_str(&reply_json).map_err(NiriError::Deserialization)?;
+    reply.map_err(NiriError::NiriErrorReply)
+}
 
-    match reply {
-        Ok(response) => {
-            if let Some(data) = expected_response_fn(response.clone()) {
-                Ok(data)
-            } else {
-                Err(NiriError::UnexpectedResponse {
-                    expected: expected_name,
-                    got: response,
-                })
-            }
-        }
-        Err(e) => Err(NiriError::NiriErrorReply(e)),
+fn send_request<T>(
+    socket_path: &Path,
+    request: Request,
+    expected_response_fn: fn(Response) -> Option<T>,
+    expected_name: &'static str,
+) -> Result<T, NiriError> {
+    let stream = UnixStream::connect(socket_path).map_err(NiriError::Connection)?;
+    let mut reader = BufReader::new(stream.try_clone().map_err(NiriError::Connection)?);
+
+    write_request(&stream, &request)?;
+    drop(stream);
+
+    let response = read_reply(&mut reader)?;
+    if let Some(data) = expected_response_fn(response.clone()) {
+        Ok(data)
+    } else {
+        Err(NiriError::UnexpectedResponse {
+            expected: expected_name,
+            got: response,
+        })
     }
 }
 
 pub fn get_focused_window() -> Result<Option<Window>, NiriError> {
+    get_focused_window_at(&socket_path()?)
+}
+
+fn get_focused_window_at(socket_path: &Path) -> Result<Option<Window>, NiriError> {
     send_request(
+        socket_path,
         Request::FocusedWindow,
         |resp| match resp {
             Response::FocusedWindow(window_option) => Some(window_option),
@@ -123,7 +139,12 @@ pub fn get_focused_window() -> Result<Option<Window>, NiriError> {
 }
 
 pub fn get_windows() -> Result<Vec<Window>, NiriError> {
+    get_windows_at(&socket_path()?)
+}
+
+fn get_windows_at(socket_path: &Path) -> Result<Vec<Window>, NiriError> {
     send_request(
+        socket_path,
         Request::Windows,
         |resp| match resp {
             Response::Windows(windows) => Some(windows),
@@ -132,3 +153,609 @@ pub fn get_windows() -> Result<Vec<Window>, NiriError> {
         "Windows",
     )
 }
+
+pub fn get_workspaces() -> Result<Vec<Workspace>, NiriError> {
+    send_request(
+        &socket_path()?,
+        Request::Workspaces,
+        |resp| match resp {
+            Response::Workspaces(workspaces) => Some(workspaces),
+            _ => None,
+        },
+        "Workspaces",
+    )
+}
+
+pub fn send_action(action: Action) -> Result<(), NiriError> {
+    send_request(
+        &socket_path()?,
+        Request::Action(action),
+        |resp| match resp {
+            Response::Handled => Some(()),
+            _ => None,
+        },
+        "Handled",
+    )
+}
+
+pub fn focus_workspace(id: u64) -> Result<(), NiriError> {
+    send_action(Action::FocusWorkspace {
+        reference: WorkspaceReferenceArg::Id(id),
+    })
+}
+
+pub fn get_outputs() -> Result<HashMap<String, Output>, NiriError> {
+    get_outputs_at(&socket_path()?)
+}
+
+fn get_outputs_at(socket_path: &Path) -> Result<HashMap<String, Output>, NiriError> {
+    send_request(
+        socket_path,
+        Request::Outputs,
+        |resp| match resp {
+            Response::Outputs(outputs) => Some(outputs),
+            _ => None,
+        },
+        "Outputs",
+    )
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NiriState {
+    pub connected: bool,
+    pub windows: HashMap<u64, Window>,
+    pub workspaces: HashMap<u64, Workspace>,
+    pub outputs: HashMap<String, Output>,
+    pub focused_window_id: Option<u64>,
+}
+
+impl NiriState {
+    pub fn focused_window(&self) -> Option<&Window> {
+        self.focused_window_id.and_then(|id| self.windows.get(&id))
+    }
+
+    pub fn apply_event(&mut self, event: Event) -> bool {
+        match event {
+            Event::WorkspacesChanged { workspaces } => {
+                self.workspaces = workspaces.into_iter().map(|ws| (ws.id, ws)).collect();
+            }
+            Event::WorkspaceActivated { id, focused } => {
+                let output = match self.workspaces.get(&id) {
+                    Some(ws) => ws.output.clone(),
+                    None => return false,
+                };
+                for ws in self.workspaces.values_mut() {
+                    if ws.output == output {
+                        ws.is_active = ws.id == id;
+                    }
+                    if focused {
+                        ws.is_focused = ws.id == id;
+                    }
+                }
+            }
+            Event::WorkspaceActiveWindowChanged {
+                workspace_id,
+                active_window_id,
+            } => match self.workspaces.get_mut(&workspace_id) {
+                Some(ws) => ws.active_window_id = active_window_id,
+                None => return false,
+            },
+            Event::WorkspaceUrgencyChanged { id, urgent } => match self.workspaces.get_mut(&id) {
+                Some(ws) => ws.is_urgent = urgent,
+                None => return false,
+            },
+            Event::WindowsChanged { windows } => {
+                self.focused_window_id = windows.iter().find(|w| w.is_focused).map(|w| w.id);
+                self.windows = windows.into_iter().map(|w| (w.id, w)).collect();
+            }
+            Event::WindowOpenedOrChanged { window } => {
+                let id = window.id;
+                let is_focused = window.is_focused;
+                self.windows.insert(id, window);
+                if is_focused {
+                    self.set_focused_window(Some(id));
+                }
+            }
+            Event::WindowClosed { id } => {
+                self.windows.remove(&id);
+                if self.focused_window_id == Some(id) {
+                    self.focused_window_id = None;
+                }
+            }
+            Event::WindowFocusChanged { id } => self.set_focused_window(id),
+            Event::WindowUrgencyChanged { id, urgent } => match self.windows.get_mut(&id) {
+                Some(window) => window.is_urgent = urgent,
+                None => return false,
+            },
+            Event::WindowLayoutsChanged { changes } => {
+                let mut changed = false;
+                for (id, layout) in changes {
+                    if let Some(window) = self.windows.get_mut(&id) {
+                        window.layout = layout;
+                        changed = true;
+                    }
+                }
+                return changed;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn to_compositor_state(&self) -> CompositorState {
+        CompositorState {
+            connected: self.connected,
+            windows: self
+                .windows
+                .iter()
+                .map(|(id, window)| (*id, window_info(window)))
+                .collect(),
+            workspaces: self
+                .workspaces
+                .iter()
+                .map(|(id, ws)| (*id, workspace_info(ws)))
+                .collect(),
+            outputs: self
+                .outputs
+                .iter()
+                .map(|(name, output)| (name.clone(), output_info(output)))
+                .collect(),
+            focused_window_id: self.focused_window_id,
+        }
+    }
+
+    fn set_focused_window(&mut self, id: Option<u64>) {
+        self.focused_window_id = id;
+        for window in self.windows.values_mut() {
+            window.is_focused = Some(window.id) == id;
+        }
+    }
+}
+
+fn window_info(window: &Window) -> WindowInfo {
+    WindowInfo {
+        id: window.id,
+        title: window.title.clone(),
+        app_id: window.app_id.clone(),
+        pid: window.pid,
+        workspace_id: window.workspace_id,
+        is_focused: window.is_focused,
+        is_floating: window.is_floating,
+        is_urgent: window.is_urgent,
+        // niri-ipc 25.8 does not expose fullscreen state. Guessing it from the tile size
+        // would mistake gapless maximized columns for fullscreen, so it is left unset;
+        // fullscreen windows are tiled, which intellihide already treats as obstructing.
+        is_fullscreen: false,
+        geometry: Some(WindowGeometry {
+            position: window.layout.tile_pos_in_workspace_view,
+            size: window.layout.tile_size,
+        }),
+    }
+}
+
+fn workspace_info(ws: &Workspace) -> WorkspaceInfo {
+    WorkspaceInfo {
+        id: ws.id,
+        idx: ws.idx as u32,
+        name: ws.name.clone(),
+        output: ws.output.clone(),
+        is_active: ws.is_active,
+        is_focused: ws.is_focused,
+        is_urgent: ws.is_urgent,
+        active_window_id: ws.active_window_id,
+    }
+}
+
+fn output_info(output: &Output) -> OutputInfo {
+    let (x, y, width, height, scale) = output
+        .logical
+        .as_ref()
+        .map(|l| (l.x, l.y, l.width, l.height, l.scale))
+        .unwrap_or((0, 0, 0, 0, 1.0));
+    OutputInfo {
+        name: output.name.clone(),
+        make: output.make.clone(),
+        model: output.model.clone(),
+        x,
+        y,
+        width,
+        height,
+        scale,
+    }
+}
+
+impl From<NiriError> for CompositorError {
+    fn from(err: NiriError) -> Self {
+        match err {
+            NiriError::SocketPathNotSet => CompositorError::NotRunning("niri"),
+            NiriError::Deserialization(e) => CompositorError::InvalidReply(e.to_string()),
+            other => CompositorError::Ipc(other.to_string()),
+        }
+    }
+}
+
+pub struct NiriCompositor;
+
+impl Compositor for NiriCompositor {
+    fn name(&self) -> &'static str {
+        "niri"
+    }
+
+    fn focused_window(&self) -> Result<Option<WindowInfo>, CompositorError> {
+        Ok(get_focused_window()?.as_ref().map(window_info))
+    }
+
+    fn windows(&self) -> Result<Vec<WindowInfo>, CompositorError> {
+        Ok(get_windows()?.iter().map(window_info).collect())
+    }
+
+    fn workspaces(&self) -> Result<Vec<WorkspaceInfo>, CompositorError> {
+        Ok(get_workspaces()?.iter().map(workspace_info).collect())
+    }
+
+    fn outputs(&self) -> Result<Vec<OutputInfo>, CompositorError> {
+        Ok(get_outputs()?.values().map(output_info).collect())
+    }
+
+    fn focus_window(&self, id: u64) -> Result<(), CompositorError> {
+        Ok(send_action(Action::FocusWindow { id })?)
+    }
+
+    fn close_window(&self, id: u64) -> Result<(), CompositorError> {
+        Ok(send_action(Action::CloseWindow { id: Some(id) })?)
+    }
+
+    fn focus_workspace(&self, id: u64) -> Result<(), CompositorError> {
+        Ok(focus_workspace(id)?)
+    }
+
+    fn subscribe(&self) -> watch::Receiver<CompositorState> {
+        subscribe()
+    }
+}
+
+pub fn subscribe() -> watch::Receiver<CompositorState> {
+    STATE_STREAM.subscribe(read_event_stream)
+}
+
+fn read_event_stream(tx: &watch::Sender<CompositorState>) -> Result<(), CompositorError> {
+    read_event_stream_at(&socket_path()?, tx)
+}
+
+fn read_event_stream_at(
+    socket_path: &Path,
+    tx: &watch::Sender<CompositorState>,
+) -> Result<(), CompositorError> {
+    let mut state = NiriState {
+        connected: true,
+        outputs: get_outputs_at(socket_path)?,
+        ..NiriState::default()
+    };
+
+    let stream = UnixStream::connect(socket_path).map_err(NiriError::Connection)?;
+    let mut reader = BufReader::new(stream.try_clone().map_err(NiriError::Connection)?);
+
+    write_request(&stream, &Request::EventStream)?;
+    match read_reply(&mut reader)? {
+        Response::Handled => {}
+        other => {
+            return Err(NiriError::UnexpectedResponse {
+                expected: "Handled",
+                got: other,
+            }
+            .into())
+        }
+    }
+
+    tx.send_replace(state.to_compositor_state());
+
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).map_err(NiriError::IPC)? == 0 {
+            return Ok(());
+        }
+
+        // Newer niri versions may send events this niri-ipc release does not know about.
+        let event: Event = match serde_json::from_str(&line) {
+            Ok(event) => event,
+            Err(_) => continue,
+        };
+
+        let refresh_outputs = matches!(event, Event::WorkspacesChanged { .. });
+        let mut changed = state.apply_event(event);
+
+        if refresh_outputs {
+            if let Ok(outputs) = get_outputs_at(socket_path) {
+                state.outputs = outputs;
+                changed = true;
+            }
+        }
+
+        if changed {
+            tx.send_replace(state.to_compositor_state());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::niri_mock::{MockConnection, MockNiri};
+
+    fn window(id: u64, app_id: &str, is_focused: bool) -> Window {
+        Window {
+            id,
+            title: Some(format!("{} window", app_id)),
+            app_id: Some(app_id.to_string()),
+            pid: Some(1000 + id as i32),
+            workspace_id: Some(1),
+            is_focused,
+            is_floating: false,
+            is_urgent: false,
+            layout: WindowLayout {
+                pos_in_scrolling_layout: Some((id as usize, 1)),
+                tile_size: (960.0, 1080.0),
+                window_size: (960, 1080),
+                tile_pos_in_workspace_view: None,
+                window_offset_in_tile: (0.0, 0.0),
+            },
+        }
+    }
+
+    fn workspace(id: u64, idx: u8, output: &str, is_active: bool) -> Workspace {
+        Workspace {
+            id,
+            idx,
+            name: None,
+            output: Some(output.to_string()),
+            is_urgent: false,
+            is_active,
+            is_focused: is_active,
+            active_window_id: None,
+        }
+    }
+
+    #[test]
+    fn missing_socket_is_a_connection_error() {
+        let path = env::temp_dir().join("kaneru-niri-mock-does-not-exist.sock");
+        let result = get_windows_at(&path);
+        assert!(matches!(result, Err(NiriError::Connection(_))));
+    }
+
+    #[test]
+    fn empty_reply_is_an_ipc_error() {
+        let mock = MockNiri::start(vec![MockConnection::Raw(String::new())]);
+        let result = get_windows_at(mock.socket_path());
+        match result {
+            Err(NiriError::IPC(e)) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof),
+            other => panic!("expected IPC error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn err_reply_is_surfaced() {
+        let mock = MockNiri::start(vec![MockConnection::Reply(Err("boom".to_string()))]);
+        let result = get_focused_window_at(mock.socket_path());
+        match result {
+            Err(NiriError::NiriErrorReply(msg)) => assert_eq!(msg, "boom"),
+            other => panic!("expected error reply, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unexpected_response_variant_is_rejected() {
+        let mock = MockNiri::start(vec![MockConnection::Reply(Ok(Response::Handled))]);
+        let result = get_focused_window_at(mock.socket_path());
+        match result {
+            Err(NiriError::UnexpectedResponse { expected, got }) => {
+                assert_eq!(expected, "FocusedWindow");
+                assert!(matches!(got, Response::Handled));
+            }
+            other => panic!("expected unexpected response error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn malformed_reply_is_a_deserialization_error() {
+        let mock = MockNiri::start(vec![MockConnection::Raw("not json\n".to_string())]);
+        let result = get_windows_at(mock.socket_path());
+        assert!(matches!(result, Err(NiriError::Deserialization(_))));
+    }
+
+    #[test]
+    fn windows_request_round_trips() {
+        let mock = MockNiri::start(vec![MockConnection::Reply(Ok(Response::Windows(vec![
+            window(1, "firefox", false),
+            window(2, "foot", true),
+        ])))]);
+
+        let windows = get_windows_at(mock.socket_path()).unwrap();
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[1].app_id.as_deref(), Some("foot"));
+
+        let requests = mock.finish();
+        assert_eq!(
+            requests,
+            vec![serde_json::to_string(&Request::Windows).unwrap()]
+        );
+    }
+
+    #[test]
+    fn focused_window_request_returns_window() {
+        let mock = MockNiri::start(vec![MockConnection::Reply(Ok(Response::FocusedWindow(
+            Some(window(7, "foot", true)),
+        )))]);
+
+        let focused = get_focused_window_at(mock.socket_path()).unwrap();
+        assert_eq!(focused.map(|w| w.id), Some(7));
+    }
+
+    #[test]
+    fn windows_changed_tracks_focused_window() {
+        let mut state = NiriState::default();
+        assert!(state.apply_event(Event::WindowsChanged {
+            windows: vec![window(1, "firefox", false), window(2, "foot", true)],
+        }));
+
+        assert_eq!(state.focused_window_id, Some(2));
+        assert_eq!(
+            state.focused_window().and_then(|w| w.app_id.as_deref()),
+            Some("foot")
+        );
+    }
+
+    #[test]
+    fn focus_change_updates_every_window() {
+        let mut state = NiriState::default();
+        state.apply_event(Event::WindowsChanged {
+            windows: vec![window(1, "firefox", false), window(2, "foot", true)],
+        });
+
+        state.apply_event(Event::WindowFocusChanged { id: Some(1) });
+        assert_eq!(state.focused_window_id, Some(1));
+        assert!(state.windows[&1].is_focused);
+        assert!(!state.windows[&2].is_focused);
+
+        state.apply_event(Event::WindowFocusChanged { id: None });
+        assert!(state.focused_window().is_none());
+        assert!(state.windows.values().all(|w| !w.is_focused));
+    }
+
+    #[test]
+    fn opened_focused_window_steals_focus() {
+        let mut state = NiriState::default();
+        state.apply_event(Event::WindowsChanged {
+            windows: vec![window(1, "firefox", true)],
+        });
+
+        state.apply_event(Event::WindowOpenedOrChanged {
+            window: window(3, "nautilus", true),
+        });
+        assert_eq!(state.focused_window_id, Some(3));
+        assert!(!state.windows[&1].is_focused);
+    }
+
+    #[test]
+    fn closing_focused_window_clears_focus() {
+        let mut state = NiriState::default();
+        state.apply_event(Event::WindowsChanged {
+            windows: vec![window(1, "firefox", false), window(2, "foot", true)],
+        });
+
+        state.apply_event(Event::WindowClosed { id: 2 });
+        assert!(state.focused_window().is_none());
+        assert_eq!(state.windows.len(), 1);
+
+        assert!(!state.apply_event(Event::WorkspaceActiveWindowChanged {
+            workspace_id: 42,
+            active_window_id: None,
+        }));
+    }
+
+    #[test]
+    fn layout_changes_update_window_geometry() {
+        let mut state = NiriState::default();
+        state.apply_event(Event::WindowsChanged {
+            windows: vec![window(1, "firefox", true)],
+        });
+
+        let mut layout = window(1, "firefox", true).layout;
+        layout.tile_size = (400.0, 300.0);
+        layout.tile_pos_in_workspace_view = Some((100.0, 50.0));
+        assert!(state.apply_event(Event::WindowLayoutsChanged {
+            changes: vec![(1, layout.clone()), (42, layout)],
+        }));
+
+        let geometry = state.to_compositor_state().windows[&1].geometry;
+        assert_eq!(
+            geometry,
+            Some(WindowGeometry {
+                position: Some((100.0, 50.0)),
+                size: (400.0, 300.0),
+            })
+        );
+
+        assert!(!state.apply_event(Event::WindowLayoutsChanged {
+            changes: vec![(42, window(42, "foot", false).layout)],
+        }));
+    }
+
+    #[test]
+    fn tiled_windows_have_no_position() {
+        let mut state = NiriState::default();
+        state.apply_event(Event::WindowsChanged {
+            windows: vec![window(1, "firefox", true)],
+        });
+
+        let firefox = &state.to_compositor_state().windows[&1];
+        assert!(!firefox.is_fullscreen);
+        assert_eq!(
+            firefox.geometry,
+            Some(WindowGeometry {
+                position: None,
+                size: (960.0, 1080.0),
+            })
+        );
+    }
+
+    #[test]
+    fn workspace_activation_is_per_output() {
+        let mut state = NiriState::default();
+        state.apply_event(Event::WorkspacesChanged {
+            workspaces: vec![
+                workspace(1, 1, "eDP-1", true),
+                workspace(2, 2, "eDP-1", false),
+                workspace(3, 1, "HDMI-A-1", true),
+            ],
+        });
+
+        state.apply_event(Event::WorkspaceActivated {
+            id: 2,
+            focused: true,
+        });
+        assert!(!state.workspaces[&1].is_active);
+        assert!(state.workspaces[&2].is_active);
+        assert!(state.workspaces[&2].is_focused);
+        assert!(state.workspaces[&3].is_active);
+        assert!(!state.workspaces[&3].is_focused);
+    }
+
+    #[test]
+    fn event_stream_publishes_compositor_state() {
+        let mock = MockNiri::start(vec![
+            MockConnection::Reply(Ok(Response::Outputs(HashMap::new()))),
+            MockConnection::EventStream(vec![
+                Event::WindowsChanged {
+                    windows: vec![window(1, "firefox", true), window(2, "foot", false)],
+                },
+                Event::WindowFocusChanged { id: Some(2) },
+                Event::WindowOpenedOrChanged {
+                    window: Window {
+                        title: Some("renamed".to_string()),
+                        ..window(2, "foot", true)
+                    },
+                },
+            ]),
+        ]);
+        let (tx, rx) = watch::channel(CompositorState::default());
+
+        read_event_stream_at(mock.socket_path(), &tx).unwrap();
+
+        let state = rx.borrow();
+        assert!(state.connected);
+        assert_eq!(state.windows.len(), 2);
+        let focused = state.focused_window().unwrap();
+        assert_eq!(focused.id, 2);
+        assert_eq!(focused.title.as_deref(), Some("renamed"));
+
+        let requests = mock.finish();
+        assert_eq!(
+            requests,
+            vec![
+                serde_json::to_string(&Request::Outputs).unwrap(),
+                serde_json::to_string(&Request::EventStream).unwrap(),
+            ]
+        );
+    }
+}
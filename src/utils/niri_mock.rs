@@ -0,0 +1,110 @@
+use niri_ipc::{Event, Reply, Response};
+use std::{
+    env, fs,
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixListener,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+static NEXT_SOCKET_ID: AtomicU32 = AtomicU32::new(0);
+
+pub enum MockConnection {
+    Reply(Reply),
+    Raw(String),
+    EventStream(Vec<Event>),
+}
+
+pub struct MockNiri {
+    socket_path: PathBuf,
+    requests: Arc<Mutex<Vec<String>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockNiri {
+    pub fn start(script: Vec<MockConnection>) -> Self {
+        let socket_path = env::temp_dir().join(format!(
+            "kaneru-niri-mock-{}-{}.sock",
+            process::id(),
+            NEXT_SOCKET_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_file(&socket_path);
+        let listener = UnixListener::bind(&socket_path).expect("Failed to bind mock niri socket");
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let requests_clone = requests.clone();
+
+        let handle = thread::spawn(move || {
+            for connection in script {
+                let (stream, _) = match listener.accept() {
+                    Ok(accepted) => accepted,
+                    Err(_) => return,
+                };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                if reader.read_line(&mut request).is_err() {
+                    continue;
+                }
+                requests_clone
+                    .lock()
+                    .unwrap()
+                    .push(request.trim_end().to_string());
+
+                let mut writer = stream;
+                let lines = match connection {
+                    MockConnection::Reply(reply) => {
+                        vec![serde_json::to_string(&reply).unwrap() + "\n"]
+                    }
+                    MockConnection::Raw(raw) => vec![raw],
+                    MockConnection::EventStream(events) => {
+                        let handled: Reply = Ok(Response::Handled);
+                        let mut lines = vec![serde_json::to_string(&handled).unwrap() + "\n"];
+                        lines.extend(
+                            events
+                                .iter()
+                                .map(|event| serde_json::to_string(event).unwrap() + "\n"),
+                        );
+                        lines
+                    }
+                };
+                for line in lines {
+                    if writer.write_all(line.as_bytes()).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        Self {
+            socket_path,
+            requests,
+            handle: Some(handle),
+        }
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub fn finish(mut self) -> Vec<String> {
+        if let Some(handle) = self.handle.take() {
+            handle.join().expect("Mock niri server panicked");
+        }
+        self.requests()
+    }
+}
+
+impl Drop for MockNiri {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.socket_path);
+    }
+}
@@ -1,8 +1,9 @@
-use crate::utils::notification_impl::Notification;
+use crate::utils::{app_usage::AppUsage, notification_impl::Notification};
 use serde::{Deserialize, Serialize};
 use std::{error::Error, fmt, fs, io, path::PathBuf};
 
 const NOTIFICATIONS_FILENAME: &str = "notifications.json";
+const APP_USAGE_FILENAME: &str = "app-usage.json";
 
 #[derive(Debug)]
 pub enum PersistenceError {
@@ -73,6 +74,36 @@ pub fn load_notifications() -> Result<Vec<Notification>, PersistenceError> {
     Ok(notifications)
 }
 
+fn get_app_usage_path() -> Result<PathBuf, PersistenceError> {
+    let mut path = dirs::state_dir().ok_or_else(|| {
+        PersistenceError::DirectoryError("Could not determine state directory".into())
+    })?;
+    path.push(env!("CARGO_PKG_NAME"));
+    fs::create_dir_all(&path)?;
+    path.push(APP_USAGE_FILENAME);
+    Ok(path)
+}
+
+pub fn save_app_usage(usage: &AppUsage) -> Result<(), PersistenceError> {
+    let path = get_app_usage_path()?;
+    let json_data = serde_json::to_string_pretty(usage)?;
+    fs::write(path, json_data)?;
+    Ok(())
+}
+
+pub fn load_app_usage() -> Result<AppUsage, PersistenceError> {
+    let path = get_app_usage_path()?;
+    if !path.exists() {
+        return Ok(AppUsage::default());
+    }
+    let json_data = fs::read_to_string(path)?;
+    if json_data.trim().is_empty() {
+        return Ok(AppUsage::default());
+    }
+    let usage = serde_json::from_str(&json_data)?;
+    Ok(usage)
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum UrgencySerde {
     Low = 0,
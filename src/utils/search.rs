@@ -0,0 +1,355 @@
+use crate::utils::{
+    app_index::{self, AppIndex},
+    calculator,
+    compositor::{self, CompositorState},
+    config::SearchConfig,
+    fuzzy::fuzzy_score,
+    icons, launcher,
+};
+use gtk4::prelude::*;
+use gtk4::{gdk, gio, glib, RecentInfo, RecentManager};
+use std::{cell::RefCell, rc::Rc};
+use tokio::sync::watch;
+
+const OPERATORS: [char; 8] = ['+', '-', '*', '/', '%', '^', '×', '÷'];
+
+#[derive(Debug, Clone)]
+pub enum SearchAction {
+    CopyText(String),
+    RunCommand(String),
+    FocusWindow(u64),
+    OpenUri(String),
+}
+
+impl SearchAction {
+    pub fn activate(&self) {
+        match self {
+            SearchAction::CopyText(text) => match gdk::Display::default() {
+                Some(display) => display.clipboard().set_text(text),
+                None => eprintln!("No display to copy '{}' to", text),
+            },
+            SearchAction::RunCommand(command) => {
+                let argv = ["sh".to_string(), "-c".to_string(), command.clone()];
+                if let Err(e) = launcher::spawn(&argv) {
+                    eprintln!("Failed to run '{}': {}", command, e);
+                }
+            }
+            SearchAction::FocusWindow(id) => {
+                if let Err(e) = compositor::compositor().focus_window(*id) {
+                    eprintln!("Failed to focus window {}: {}", id, e);
+                }
+            }
+            SearchAction::OpenUri(uri) => {
+                let context = gdk::Display::default().map(|display| display.app_launch_context());
+                if let Err(e) = gio::AppInfo::launch_default_for_uri(uri, context.as_ref()) {
+                    eprintln!("Failed to open {}: {}", uri, e);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub icon: gio::Icon,
+    pub score: i32,
+    pub action: SearchAction,
+}
+
+pub trait SearchProvider {
+    /// Section title shown above the provider's results.
+    fn title(&self) -> &'static str;
+    /// Typing this first restricts the search to this provider.
+    fn prefix(&self) -> &str;
+    /// Whether the provider also answers queries typed without its prefix.
+    fn answers_unprefixed(&self) -> bool {
+        true
+    }
+    /// Whether the section is listed above the applications.
+    fn before_apps(&self) -> bool {
+        false
+    }
+    /// Results for `query` with the prefix stripped, best first.
+    fn search(&self, query: &str, limit: usize) -> Vec<SearchResult>;
+}
+
+pub fn providers(config: &SearchConfig) -> Vec<Box<dyn SearchProvider>> {
+    vec![
+        Box::new(CalculatorProvider {
+            prefix: config.calculator_prefix.clone(),
+        }),
+        Box::new(CommandProvider {
+            prefix: config.command_prefix.clone(),
+        }),
+        Box::new(WindowsProvider {
+            prefix: config.windows_prefix.clone(),
+            state_rx: compositor::compositor().subscribe(),
+            apps_rx: app_index::subscribe(),
+        }),
+        Box::new(RecentFilesProvider::new(config.files_prefix.clone())),
+    ]
+}
+
+/// Finds the provider whose prefix `query` starts with, preferring the
+/// longest prefix, and returns it with the rest of the query.
+pub fn route<'a>(
+    providers: &[Box<dyn SearchProvider>],
+    query: &'a str,
+) -> (Option<usize>, &'a str) {
+    providers
+        .iter()
+        .enumerate()
+        .filter(|(_, provider)| !provider.prefix().is_empty())
+        .filter_map(|(i, provider)| {
+            let rest = query.strip_prefix(provider.prefix())?;
+            Some((i, provider.prefix().len(), rest))
+        })
+        .max_by_key(|(_, len, _)| *len)
+        .map_or((None, query), |(i, _, rest)| (Some(i), rest))
+}
+
+fn themed(name: &str) -> gio::Icon {
+    gio::ThemedIcon::new(name).upcast()
+}
+
+fn best_first(mut results: Vec<SearchResult>, limit: usize) -> Vec<SearchResult> {
+    results.sort_by(|a, b| b.score.cmp(&a.score));
+    results.truncate(limit);
+    results
+}
+
+struct CalculatorProvider {
+    prefix: String,
+}
+
+impl SearchProvider for CalculatorProvider {
+    fn title(&self) -> &'static str {
+        "Calculator"
+    }
+
+    fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    fn before_apps(&self) -> bool {
+        true
+    }
+
+    fn search(&self, query: &str, _limit: usize) -> Vec<SearchResult> {
+        // A bare number is more likely an app name fragment than a sum.
+        if !query.contains(OPERATORS) {
+            return Vec::new();
+        }
+        let Ok(value) = calculator::evaluate(query) else {
+            return Vec::new();
+        };
+        let result = calculator::format_number(value);
+        vec![SearchResult {
+            title: format!("= {}", result),
+            subtitle: Some(format!("{} · Enter to copy", query.trim())),
+            icon: themed("accessories-calculator-symbolic"),
+            score: 0,
+            action: SearchAction::CopyText(result),
+        }]
+    }
+}
+
+struct CommandProvider {
+    prefix: String,
+}
+
+impl SearchProvider for CommandProvider {
+    fn title(&self) -> &'static str {
+        "Run"
+    }
+
+    fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    fn answers_unprefixed(&self) -> bool {
+        false
+    }
+
+    fn before_apps(&self) -> bool {
+        true
+    }
+
+    fn search(&self, query: &str, _limit: usize) -> Vec<SearchResult> {
+        let command = query.trim();
+        if command.is_empty() {
+            return Vec::new();
+        }
+        vec![SearchResult {
+            title: command.to_string(),
+            subtitle: Some("Run command".to_string()),
+            icon: themed("utilities-terminal-symbolic"),
+            score: 0,
+            action: SearchAction::RunCommand(command.to_string()),
+        }]
+    }
+}
+
+struct WindowsProvider {
+    prefix: String,
+    state_rx: watch::Receiver<CompositorState>,
+    apps_rx: watch::Receiver<AppIndex>,
+}
+
+impl SearchProvider for WindowsProvider {
+    fn title(&self) -> &'static str {
+        "Open windows"
+    }
+
+    fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        let state = self.state_rx.borrow();
+        let apps = self.apps_rx.borrow();
+        let results = state
+            .windows
+            .values()
+            .filter_map(|window| {
+                let title = window.title.clone().unwrap_or_default();
+                let app_id = window.app_id.clone().unwrap_or_default();
+                let score = fuzzy_score(query, &format!("{} {}", title, app_id))?;
+                let app = apps
+                    .as_ref()
+                    .and_then(|apps| apps.resolve_by_app_id(&app_id));
+                Some(SearchResult {
+                    icon: icons::app_icon(app, &app_id),
+                    subtitle: Some(app.map_or(app_id.clone(), |app| app.name.clone())),
+                    title: if title.is_empty() { app_id } else { title },
+                    score,
+                    action: SearchAction::FocusWindow(window.id),
+                })
+            })
+            .collect();
+        best_first(results, limit)
+    }
+}
+
+/// Recently used files, read by GTK from `recently-used.xbel`.
+struct RecentFilesProvider {
+    prefix: String,
+    manager: RecentManager,
+    /// Files that still exist, most recent first. Checking that touches the
+    /// disk, so it happens when the list changes rather than per keystroke.
+    items: Rc<RefCell<Vec<RecentInfo>>>,
+    changed_handler: Option<glib::SignalHandlerId>,
+}
+
+impl RecentFilesProvider {
+    fn new(prefix: String) -> Self {
+        let manager = RecentManager::default();
+        let items = Rc::new(RefCell::new(Self::load(&manager)));
+        let items_weak = Rc::downgrade(&items);
+        let changed_handler = manager.connect_changed(move |manager| {
+            if let Some(items) = items_weak.upgrade() {
+                *items.borrow_mut() = Self::load(manager);
+            }
+        });
+        Self {
+            prefix,
+            manager,
+            items,
+            changed_handler: Some(changed_handler),
+        }
+    }
+
+    fn load(manager: &RecentManager) -> Vec<RecentInfo> {
+        let mut items: Vec<_> = manager
+            .items()
+            .into_iter()
+            .filter(|info| !info.is_private_hint() && info.exists())
+            .collect();
+        // Most recent first, so ties and the empty query favour fresh files.
+        items.sort_by_key(|info| std::cmp::Reverse(info.modified().to_unix()));
+        items
+    }
+}
+
+impl Drop for RecentFilesProvider {
+    fn drop(&mut self) {
+        if let Some(handler) = self.changed_handler.take() {
+            self.manager.disconnect(handler);
+        }
+    }
+}
+
+impl SearchProvider for RecentFilesProvider {
+    fn title(&self) -> &'static str {
+        "Recent files"
+    }
+
+    fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        let results = self
+            .items
+            .borrow()
+            .iter()
+            .filter_map(|info| {
+                let name = info.display_name().to_string();
+                let score = fuzzy_score(query, &name)?;
+                let location = info.uri_display().map(|path| path.to_string());
+                Some(SearchResult {
+                    title: name,
+                    subtitle: location,
+                    icon: info.gicon().unwrap_or_else(|| themed("text-x-generic")),
+                    score,
+                    action: SearchAction::OpenUri(info.uri().to_string()),
+                })
+            })
+            .collect();
+        best_first(results, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prefixed() -> Vec<Box<dyn SearchProvider>> {
+        vec![
+            Box::new(CalculatorProvider {
+                prefix: "=".to_string(),
+            }),
+            Box::new(CommandProvider {
+                prefix: ">".to_string(),
+            }),
+            Box::new(CommandProvider {
+                prefix: ">>".to_string(),
+            }),
+        ]
+    }
+
+    #[test]
+    fn routes_by_longest_prefix() {
+        let providers = prefixed();
+        assert_eq!(route(&providers, "=2+2"), (Some(0), "2+2"));
+        assert_eq!(route(&providers, ">ls"), (Some(1), "ls"));
+        assert_eq!(route(&providers, ">>ls"), (Some(2), "ls"));
+        assert_eq!(route(&providers, "firefox"), (None, "firefox"));
+    }
+
+    #[test]
+    fn calculator_only_answers_expressions() {
+        let calculator = CalculatorProvider {
+            prefix: "=".to_string(),
+        };
+        assert!(calculator.search("42", 5).is_empty());
+        assert!(calculator.search("gnome-terminal", 5).is_empty());
+
+        let results = calculator.search("6 * 7", 5);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "= 42");
+        assert!(matches!(&results[0].action, SearchAction::CopyText(text) if text == "42"));
+    }
+}
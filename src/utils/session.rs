@@ -0,0 +1,226 @@
+use std::{error::Error, fmt};
+use tokio::sync::OnceCell;
+use zbus::{proxy, Connection};
+
+/// Asks logind for the session the caller belongs to, falling back to the
+/// user's graphical session.
+const CURRENT_SESSION: &str = "auto";
+
+static SHARED: OnceCell<SessionService> = OnceCell::const_new();
+
+#[derive(Debug, Clone)]
+pub enum SessionError {
+    Zbus(String),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Zbus(e) => write!(f, "D-Bus error: {}", e),
+        }
+    }
+}
+
+impl Error for SessionError {}
+
+impl From<zbus::Error> for SessionError {
+    fn from(err: zbus::Error) -> Self {
+        SessionError::Zbus(err.to_string())
+    }
+}
+
+#[proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait Login1Manager {
+    fn can_suspend(&self) -> zbus::Result<String>;
+    fn can_hibernate(&self) -> zbus::Result<String>;
+    fn can_reboot(&self) -> zbus::Result<String>;
+    fn can_power_off(&self) -> zbus::Result<String>;
+    fn suspend(&self, interactive: bool) -> zbus::Result<()>;
+    fn hibernate(&self, interactive: bool) -> zbus::Result<()>;
+    fn reboot(&self, interactive: bool) -> zbus::Result<()>;
+    fn power_off(&self, interactive: bool) -> zbus::Result<()>;
+    fn lock_session(&self, session_id: &str) -> zbus::Result<()>;
+    fn terminate_session(&self, session_id: &str) -> zbus::Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SessionAction {
+    Lock,
+    LogOut,
+    Suspend,
+    Hibernate,
+    Reboot,
+    PowerOff,
+}
+
+impl SessionAction {
+    pub fn label(self) -> &'static str {
+        match self {
+            SessionAction::Lock => "Lock",
+            SessionAction::LogOut => "Log out",
+            SessionAction::Suspend => "Suspend",
+            SessionAction::Hibernate => "Hibernate",
+            SessionAction::Reboot => "Reboot",
+            SessionAction::PowerOff => "Power off",
+        }
+    }
+
+    pub fn icon_name(self) -> &'static str {
+        match self {
+            SessionAction::Lock => "system-lock-screen-symbolic",
+            SessionAction::LogOut => "system-log-out-symbolic",
+            SessionAction::Suspend => "weather-clear-night-symbolic",
+            SessionAction::Hibernate => "drive-harddisk-symbolic",
+            SessionAction::Reboot => "system-reboot-symbolic",
+            SessionAction::PowerOff => "system-shutdown-symbolic",
+        }
+    }
+
+    /// Actions that end the session and lose unsaved work.
+    pub fn is_destructive(self) -> bool {
+        matches!(
+            self,
+            SessionAction::LogOut | SessionAction::Reboot | SessionAction::PowerOff
+        )
+    }
+}
+
+/// `challenge` means polkit will ask for authentication, which logind
+/// handles because every call is made interactive.
+fn is_allowed(answer: &str) -> bool {
+    matches!(answer, "yes" | "challenge")
+}
+
+#[derive(Clone)]
+pub struct SessionService {
+    manager: Login1ManagerProxy<'static>,
+}
+
+impl SessionService {
+    pub async fn system() -> Result<Self, SessionError> {
+        Self::new(&Connection::system().await?).await
+    }
+
+    /// The system bus service shared by every power menu. A failed
+    /// connection is retried on the next call.
+    pub async fn shared() -> Result<Self, SessionError> {
+        SHARED.get_or_try_init(Self::system).await.cloned()
+    }
+
+    pub async fn new(connection: &Connection) -> Result<Self, SessionError> {
+        let manager = Login1ManagerProxy::new(connection).await?;
+        Ok(Self { manager })
+    }
+
+    /// Lock and Log out are always offered; the rest only when logind
+    /// reports them as possible.
+    pub async fn available_actions(&self) -> Vec<SessionAction> {
+        let mut actions = vec![SessionAction::Lock, SessionAction::LogOut];
+        let (suspend, hibernate, reboot, power_off) = tokio::join!(
+            self.manager.can_suspend(),
+            self.manager.can_hibernate(),
+            self.manager.can_reboot(),
+            self.manager.can_power_off(),
+        );
+        let checks = [
+            (SessionAction::Suspend, suspend),
+            (SessionAction::Hibernate, hibernate),
+            (SessionAction::Reboot, reboot),
+            (SessionAction::PowerOff, power_off),
+        ];
+        for (action, answer) in checks {
+            match answer {
+                Ok(answer) if is_allowed(&answer) => actions.push(action),
+                Ok(_) => {}
+                Err(e) => eprintln!("Failed to check whether {:?} is possible: {}", action, e),
+            }
+        }
+        actions
+    }
+
+    pub async fn perform(&self, action: SessionAction) -> Result<(), SessionError> {
+        match action {
+            SessionAction::Lock => self.manager.lock_session(CURRENT_SESSION).await?,
+            SessionAction::LogOut => self.manager.terminate_session(CURRENT_SESSION).await?,
+            SessionAction::Suspend => self.manager.suspend(true).await?,
+            SessionAction::Hibernate => self.manager.hibernate(true).await?,
+            SessionAction::Reboot => self.manager.reboot(true).await?,
+            SessionAction::PowerOff => self.manager.power_off(true).await?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::login1_mock::MockLogin1;
+
+    #[tokio::test]
+    async fn hides_actions_logind_does_not_allow() {
+        let mock = MockLogin1::start([
+            ("CanSuspend", "yes"),
+            ("CanHibernate", "na"),
+            ("CanReboot", "challenge"),
+            ("CanPowerOff", "no"),
+        ])
+        .await;
+        let service = SessionService::new(mock.connection()).await.unwrap();
+
+        assert_eq!(
+            service.available_actions().await,
+            vec![
+                SessionAction::Lock,
+                SessionAction::LogOut,
+                SessionAction::Suspend,
+                SessionAction::Reboot,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn actions_call_login1_interactively() {
+        let mock = MockLogin1::start([]).await;
+        let service = SessionService::new(mock.connection()).await.unwrap();
+
+        service.perform(SessionAction::Lock).await.unwrap();
+        service.perform(SessionAction::PowerOff).await.unwrap();
+        service.perform(SessionAction::LogOut).await.unwrap();
+
+        assert_eq!(
+            mock.calls(),
+            vec![
+                "LockSession(auto)",
+                "PowerOff(true)",
+                "TerminateSession(auto)"
+            ]
+        );
+    }
+
+    #[test]
+    fn only_session_ending_actions_need_confirmation() {
+        let destructive: Vec<_> = [
+            SessionAction::Lock,
+            SessionAction::LogOut,
+            SessionAction::Suspend,
+            SessionAction::Hibernate,
+            SessionAction::Reboot,
+            SessionAction::PowerOff,
+        ]
+        .into_iter()
+        .filter(|action| action.is_destructive())
+        .collect();
+        assert_eq!(
+            destructive,
+            vec![
+                SessionAction::LogOut,
+                SessionAction::Reboot,
+                SessionAction::PowerOff
+            ]
+        );
+    }
+}
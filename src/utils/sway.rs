@@ -0,0 +1,639 @@
+use crate::utils::compositor::{
+    Compositor, CompositorError, CompositorState, EventReader, OutputInfo, StateStream,
+    WindowGeometry, WindowInfo, WorkspaceInfo,
+};
+use serde::Deserialize;
+use std::{
+    env,
+    io::{Read, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+};
+use tokio::sync::watch;
+
+const SOCKET_PATH_ENV: &str = "SWAYSOCK";
+const IPC_MAGIC: &[u8; 6] = b"i3-ipc";
+const IPC_HEADER_LEN: usize = 14;
+
+const RUN_COMMAND: u32 = 0;
+const GET_WORKSPACES: u32 = 1;
+const SUBSCRIBE: u32 = 2;
+const GET_OUTPUTS: u32 = 3;
+const GET_TREE: u32 = 4;
+
+const EVENT_WORKSPACE: u32 = 0x8000_0000;
+const EVENT_OUTPUT: u32 = 0x8000_0001;
+const EVENT_WINDOW: u32 = 0x8000_0003;
+
+/// The hidden output holding the scratchpad.
+const SCRATCHPAD_OUTPUT: &str = "__i3";
+
+static STATE_STREAM: StateStream = StateStream::new("sway");
+
+#[derive(Debug, Deserialize)]
+struct SwayRect {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+#[derive(Debug, Deserialize)]
+struct SwayWindowProperties {
+    #[serde(default)]
+    class: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SwayNode {
+    id: i64,
+    #[serde(rename = "type")]
+    node_type: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    focused: bool,
+    #[serde(default)]
+    urgent: bool,
+    #[serde(default)]
+    pid: Option<i32>,
+    #[serde(default)]
+    app_id: Option<String>,
+    #[serde(default)]
+    window_properties: Option<SwayWindowProperties>,
+    #[serde(default)]
+    nodes: Vec<SwayNode>,
+    #[serde(default)]
+    floating_nodes: Vec<SwayNode>,
+    #[serde(default)]
+    rect: Option<SwayRect>,
+    #[serde(default)]
+    fullscreen_mode: u8,
+}
+
+#[derive(Debug, Deserialize)]
+struct SwayWorkspace {
+    id: i64,
+    #[serde(default)]
+    num: i32,
+    name: String,
+    #[serde(default)]
+    visible: bool,
+    #[serde(default)]
+    focused: bool,
+    #[serde(default)]
+    urgent: bool,
+    #[serde(default)]
+    output: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SwayOutput {
+    name: String,
+    #[serde(default)]
+    make: String,
+    #[serde(default)]
+    model: String,
+    #[serde(default)]
+    active: bool,
+    #[serde(default = "default_scale")]
+    scale: f64,
+    rect: SwayRect,
+}
+
+#[derive(Debug, Deserialize)]
+struct SwayEvent {
+    change: String,
+    #[serde(default)]
+    container: Option<SwayNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommandResult {
+    success: bool,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+fn connect() -> Result<UnixStream, CompositorError> {
+    let socket_path = env::var(SOCKET_PATH_ENV)
+        .map(PathBuf::from)
+        .map_err(|_| CompositorError::NotRunning("sway"))?;
+    UnixStream::connect(socket_path).map_err(|e| CompositorError::Ipc(e.to_string()))
+}
+
+fn write_message(
+    stream: &mut UnixStream,
+    message_type: u32,
+    payload: &str,
+) -> Result<(), CompositorError> {
+    let mut message = Vec::with_capacity(IPC_HEADER_LEN + payload.len());
+    message.extend_from_slice(IPC_MAGIC);
+    message.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+    message.extend_from_slice(&message_type.to_ne_bytes());
+    message.extend_from_slice(payload.as_bytes());
+    stream
+        .write_all(&message)
+        .map_err(|e| CompositorError::Ipc(e.to_string()))
+}
+
+fn read_message(stream: &mut UnixStream) -> Result<(u32, Vec<u8>), CompositorError> {
+    let mut header = [0u8; IPC_HEADER_LEN];
+    stream
+        .read_exact(&mut header)
+        .map_err(|e| CompositorError::Ipc(e.to_string()))?;
+    if &header[..6] != IPC_MAGIC {
+        return Err(CompositorError::InvalidReply("bad i3-ipc magic".into()));
+    }
+
+    let len = u32::from_ne_bytes([header[6], header[7], header[8], header[9]]) as usize;
+    let message_type = u32::from_ne_bytes([header[10], header[11], header[12], header[13]]);
+
+    let mut payload = vec![0u8; len];
+    stream
+        .read_exact(&mut payload)
+        .map_err(|e| CompositorError::Ipc(e.to_string()))?;
+    Ok((message_type, payload))
+}
+
+fn request<T: for<'de> Deserialize<'de>>(
+    message_type: u32,
+    payload: &str,
+) -> Result<T, CompositorError> {
+    let mut stream = connect()?;
+    write_message(&mut stream, message_type, payload)?;
+    let (_, reply) = read_message(&mut stream)?;
+    serde_json::from_slice(&reply).map_err(|e| CompositorError::InvalidReply(e.to_string()))
+}
+
+fn run_command(command: &str) -> Result<(), CompositorError> {
+    let results: Vec<CommandResult> = request(RUN_COMMAND, command)?;
+    match results.into_iter().find(|r| !r.success) {
+        Some(failed) => Err(CompositorError::Ipc(
+            failed.error.unwrap_or_else(|| command.to_string()),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn collect_windows(
+    node: &SwayNode,
+    workspace_id: Option<u64>,
+    origin: (i32, i32),
+    windows: &mut Vec<WindowInfo>,
+) {
+    if node.node_type == "output" && node.name.as_deref() == Some(SCRATCHPAD_OUTPUT) {
+        return;
+    }
+    let workspace_id = if node.node_type == "workspace" {
+        Some(node.id as u64)
+    } else {
+        workspace_id
+    };
+    let origin = match (&node.rect, node.node_type.as_str()) {
+        (Some(rect), "output") => (rect.x, rect.y),
+        _ => origin,
+    };
+
+    let is_window = (node.node_type == "con" || node.node_type == "floating_con")
+        && node.nodes.is_empty()
+        && node.pid.is_some();
+    if is_window {
+        windows.push(window_info(node, workspace_id, origin));
+    }
+
+    for child in node.nodes.iter().chain(node.floating_nodes.iter()) {
+        collect_windows(child, workspace_id, origin, windows);
+    }
+}
+
+fn window_info(node: &SwayNode, workspace_id: Option<u64>, origin: (i32, i32)) -> WindowInfo {
+    WindowInfo {
+        id: node.id as u64,
+        title: node.name.clone(),
+        app_id: node.app_id.clone().or_else(|| {
+            node.window_properties
+                .as_ref()
+                .and_then(|props| props.class.clone())
+        }),
+        pid: node.pid,
+        workspace_id,
+        is_focused: node.focused,
+        is_floating: node.node_type == "floating_con",
+        is_urgent: node.urgent,
+        is_fullscreen: node.fullscreen_mode != 0,
+        geometry: node.rect.as_ref().map(|rect| WindowGeometry {
+            position: Some(((rect.x - origin.0) as f64, (rect.y - origin.1) as f64)),
+            size: (rect.width as f64, rect.height as f64),
+        }),
+    }
+}
+
+fn windows_from_tree(tree: &SwayNode) -> Vec<WindowInfo> {
+    let mut windows = Vec::new();
+    collect_windows(tree, None, (0, 0), &mut windows);
+    windows
+}
+
+fn workspace_info(ws: SwayWorkspace) -> WorkspaceInfo {
+    WorkspaceInfo {
+        id: ws.id as u64,
+        idx: ws.num.max(0) as u32,
+        name: Some(ws.name),
+        output: Some(ws.output),
+        is_active: ws.visible,
+        is_focused: ws.focused,
+        is_urgent: ws.urgent,
+        active_window_id: None,
+    }
+}
+
+fn output_info(o: SwayOutput) -> OutputInfo {
+    OutputInfo {
+        name: o.name,
+        make: o.make,
+        model: o.model,
+        x: o.rect.x,
+        y: o.rect.y,
+        width: o.rect.width.max(0) as u32,
+        height: o.rect.height.max(0) as u32,
+        scale: o.scale,
+    }
+}
+
+fn query_windows() -> Result<Vec<WindowInfo>, CompositorError> {
+    let tree: SwayNode = request(GET_TREE, "")?;
+    Ok(windows_from_tree(&tree))
+}
+
+fn query_workspaces() -> Result<Vec<WorkspaceInfo>, CompositorError> {
+    let workspaces: Vec<SwayWorkspace> = request(GET_WORKSPACES, "")?;
+    Ok(workspaces.into_iter().map(workspace_info).collect())
+}
+
+fn query_outputs() -> Result<Vec<OutputInfo>, CompositorError> {
+    let outputs: Vec<SwayOutput> = request(GET_OUTPUTS, "")?;
+    Ok(outputs
+        .into_iter()
+        .filter(|o| o.active)
+        .map(output_info)
+        .collect())
+}
+
+/// Which replies an event may have invalidated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Refresh {
+    tree: bool,
+    workspaces: bool,
+    outputs: bool,
+}
+
+impl Refresh {
+    const NONE: Refresh = Refresh {
+        tree: false,
+        workspaces: false,
+        outputs: false,
+    };
+    const ALL: Refresh = Refresh {
+        tree: true,
+        workspaces: true,
+        outputs: true,
+    };
+
+    fn merge(&mut self, other: Refresh) {
+        self.tree |= other.tree;
+        self.workspaces |= other.workspaces;
+        self.outputs |= other.outputs;
+    }
+}
+
+/// The last known state, kept between events so each event only re-queries
+/// what it can have changed.
+#[derive(Debug, Default)]
+struct Snapshot {
+    windows: Vec<WindowInfo>,
+    workspaces: Vec<WorkspaceInfo>,
+    outputs: Vec<OutputInfo>,
+}
+
+impl Snapshot {
+    fn refresh(&mut self, refresh: Refresh) -> Result<(), CompositorError> {
+        if refresh.tree {
+            self.windows = query_windows()?;
+        }
+        if refresh.workspaces {
+            self.workspaces = query_workspaces()?;
+        }
+        if refresh.outputs {
+            self.outputs = query_outputs()?;
+        }
+        Ok(())
+    }
+
+    /// Applies what an event payload carries and returns what must be re-queried.
+    fn apply_event(&mut self, event_type: u32, payload: &[u8]) -> Refresh {
+        let Ok(event) = serde_json::from_slice::<SwayEvent>(payload) else {
+            return Refresh::ALL;
+        };
+        match event_type {
+            EVENT_WINDOW => self.apply_window_event(&event),
+            // A workspace moved to another output moves its windows with it.
+            EVENT_WORKSPACE if event.change == "move" || event.change == "reload" => Refresh::ALL,
+            // Focusing an empty workspace sends no window focus event, so only the tree
+            // shows that the previously focused window lost focus.
+            EVENT_WORKSPACE if matches!(event.change.as_str(), "focus" | "init" | "empty") => {
+                Refresh {
+                    tree: true,
+                    workspaces: true,
+                    ..Refresh::NONE
+                }
+            }
+            EVENT_WORKSPACE => Refresh {
+                workspaces: true,
+                ..Refresh::NONE
+            },
+            EVENT_OUTPUT => Refresh::ALL,
+            _ => Refresh::NONE,
+        }
+    }
+
+    fn apply_window_event(&mut self, event: &SwayEvent) -> Refresh {
+        let tree = Refresh {
+            tree: true,
+            ..Refresh::NONE
+        };
+        let Some(container) = &event.container else {
+            return tree;
+        };
+        let id = container.id as u64;
+        if event.change == "focus" {
+            for window in &mut self.windows {
+                window.is_focused = window.id == id;
+            }
+            return Refresh::NONE;
+        }
+        let Some(window) = self.windows.iter_mut().find(|w| w.id == id) else {
+            return tree;
+        };
+        match event.change.as_str() {
+            "title" => window.title = container.name.clone(),
+            "urgent" => window.is_urgent = container.urgent,
+            "mark" => {}
+            // New, close, move, floating and fullscreen changes reflow the layout.
+            _ => return tree,
+        }
+        Refresh::NONE
+    }
+
+    fn to_state(&self) -> CompositorState {
+        let mut workspaces = self.workspaces.clone();
+        for ws in &mut workspaces {
+            ws.active_window_id = self
+                .windows
+                .iter()
+                .find(|w| w.workspace_id == Some(ws.id) && w.is_focused)
+                .map(|w| w.id);
+        }
+        CompositorState {
+            connected: true,
+            focused_window_id: self.windows.iter().find(|w| w.is_focused).map(|w| w.id),
+            windows: self.windows.iter().map(|w| (w.id, w.clone())).collect(),
+            workspaces: workspaces.into_iter().map(|ws| (ws.id, ws)).collect(),
+            outputs: self
+                .outputs
+                .iter()
+                .map(|o| (o.name.clone(), o.clone()))
+                .collect(),
+        }
+    }
+}
+
+fn read_event_stream(tx: &watch::Sender<CompositorState>) -> Result<(), CompositorError> {
+    let mut stream = connect()?;
+    write_message(&mut stream, SUBSCRIBE, r#"["window","workspace","output"]"#)?;
+    let (_, reply) = read_message(&mut stream)?;
+    let subscribed: CommandResult =
+        serde_json::from_slice(&reply).map_err(|e| CompositorError::InvalidReply(e.to_string()))?;
+    if !subscribed.success {
+        return Err(CompositorError::Ipc(
+            "sway rejected event subscription".into(),
+        ));
+    }
+
+    let mut event_stream = stream
+        .try_clone()
+        .map_err(|e| CompositorError::Ipc(e.to_string()))?;
+    let events = EventReader::spawn(&stream, move || read_message(&mut event_stream).map(Some))?;
+
+    let mut snapshot = Snapshot::default();
+    snapshot.refresh(Refresh::ALL)?;
+    tx.send_replace(snapshot.to_state());
+
+    while let Some(burst) = events.next_burst()? {
+        let mut refresh = Refresh::NONE;
+        for (event_type, payload) in &burst {
+            refresh.merge(snapshot.apply_event(*event_type, payload));
+        }
+        snapshot.refresh(refresh)?;
+
+        let state = snapshot.to_state();
+        tx.send_if_modified(|current| {
+            if *current == state {
+                false
+            } else {
+                *current = state;
+                true
+            }
+        });
+    }
+    Ok(())
+}
+
+pub struct SwayCompositor;
+
+impl Compositor for SwayCompositor {
+    fn name(&self) -> &'static str {
+        "sway"
+    }
+
+    fn focused_window(&self) -> Result<Option<WindowInfo>, CompositorError> {
+        Ok(query_windows()?.into_iter().find(|w| w.is_focused))
+    }
+
+    fn windows(&self) -> Result<Vec<WindowInfo>, CompositorError> {
+        query_windows()
+    }
+
+    fn workspaces(&self) -> Result<Vec<WorkspaceInfo>, CompositorError> {
+        query_workspaces()
+    }
+
+    fn outputs(&self) -> Result<Vec<OutputInfo>, CompositorError> {
+        query_outputs()
+    }
+
+    fn focus_window(&self, id: u64) -> Result<(), CompositorError> {
+        run_command(&format!("[con_id={}] focus", id))
+    }
+
+    fn close_window(&self, id: u64) -> Result<(), CompositorError> {
+        run_command(&format!("[con_id={}] kill", id))
+    }
+
+    fn focus_workspace(&self, id: u64) -> Result<(), CompositorError> {
+        let workspace = query_workspaces()?
+            .into_iter()
+            .find(|ws| ws.id == id)
+            .ok_or(CompositorError::UnknownWorkspace(id))?;
+        let name = workspace.name.unwrap_or_default().replace('"', "\\\"");
+        run_command(&format!("workspace \"{}\"", name))
+    }
+
+    fn subscribe(&self) -> watch::Receiver<CompositorState> {
+        STATE_STREAM.subscribe(read_event_stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A trimmed `swaymsg -t get_tree` reply: one tiled and one floating window on
+    // the second output, plus a window in the scratchpad.
+    const TREE: &str = r#"{
+        "id": 1, "type": "root", "name": "root",
+        "rect": {"x": 0, "y": 0, "width": 4480, "height": 1440},
+        "nodes": [
+            {"id": 2, "type": "output", "name": "__i3",
+             "rect": {"x": 0, "y": 0, "width": 1920, "height": 1080},
+             "nodes": [
+                {"id": 3, "type": "workspace", "name": "__i3_scratch", "nodes": [],
+                 "floating_nodes": [
+                    {"id": 9, "type": "floating_con", "name": "btop", "pid": 600,
+                     "app_id": "btop", "nodes": [],
+                     "rect": {"x": 0, "y": 0, "width": 800, "height": 600}}
+                 ]}
+             ]},
+            {"id": 4, "type": "output", "name": "DP-1",
+             "rect": {"x": 1920, "y": 0, "width": 2560, "height": 1440},
+             "nodes": [
+                {"id": 5, "type": "workspace", "name": "2",
+                 "rect": {"x": 1920, "y": 30, "width": 2560, "height": 1410},
+                 "nodes": [
+                    {"id": 6, "type": "con", "name": "~ - foot", "pid": 400,
+                     "app_id": "foot", "focused": true, "nodes": [],
+                     "rect": {"x": 1920, "y": 30, "width": 1280, "height": 1410}}
+                 ],
+                 "floating_nodes": [
+                    {"id": 7, "type": "floating_con", "name": "Steam", "pid": 500,
+                     "app_id": null, "window_properties": {"class": "steam"},
+                     "urgent": true, "fullscreen_mode": 1, "nodes": [],
+                     "rect": {"x": 2420, "y": 300, "width": 1000, "height": 700}}
+                 ]}
+             ]}
+        ]
+    }"#;
+
+    const WORKSPACES: &str = r#"[
+        {"id": 5, "num": 2, "name": "2", "visible": true, "focused": true,
+         "urgent": false, "output": "DP-1"}
+    ]"#;
+
+    fn snapshot() -> Snapshot {
+        let tree: SwayNode = serde_json::from_str(TREE).unwrap();
+        let workspaces: Vec<SwayWorkspace> = serde_json::from_str(WORKSPACES).unwrap();
+        Snapshot {
+            windows: windows_from_tree(&tree),
+            workspaces: workspaces.into_iter().map(workspace_info).collect(),
+            outputs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn parses_windows_from_captured_tree() {
+        let state = snapshot().to_state();
+
+        assert_eq!(state.windows.len(), 2, "scratchpad windows are hidden");
+        let foot = &state.windows[&6];
+        assert_eq!(foot.workspace_id, Some(5));
+        assert_eq!(
+            foot.geometry,
+            Some(WindowGeometry {
+                position: Some((0.0, 30.0)),
+                size: (1280.0, 1410.0),
+            })
+        );
+
+        let steam = &state.windows[&7];
+        assert_eq!(steam.app_id.as_deref(), Some("steam"));
+        assert!(steam.is_floating && steam.is_urgent && steam.is_fullscreen);
+
+        assert_eq!(state.focused_window_id, Some(6));
+        assert_eq!(state.workspaces[&5].active_window_id, Some(6));
+    }
+
+    #[test]
+    fn window_events_update_in_place() {
+        let mut snapshot = snapshot();
+
+        let title = br#"{"change": "title", "container": {"id": 6, "type": "con", "name": "vim"}}"#;
+        assert_eq!(snapshot.apply_event(EVENT_WINDOW, title), Refresh::NONE);
+
+        let focus = br#"{"change": "focus", "container": {"id": 7, "type": "floating_con"}}"#;
+        assert_eq!(snapshot.apply_event(EVENT_WINDOW, focus), Refresh::NONE);
+
+        let state = snapshot.to_state();
+        assert_eq!(state.windows[&6].title.as_deref(), Some("vim"));
+        assert_eq!(state.focused_window_id, Some(7));
+
+        let new = br#"{"change": "new", "container": {"id": 8, "type": "con"}}"#;
+        assert_eq!(
+            snapshot.apply_event(EVENT_WINDOW, new),
+            Refresh {
+                tree: true,
+                ..Refresh::NONE
+            }
+        );
+        let renamed = br#"{"change": "rename", "current": {"id": 5, "type": "workspace"}}"#;
+        assert_eq!(
+            snapshot.apply_event(EVENT_WORKSPACE, renamed),
+            Refresh {
+                workspaces: true,
+                ..Refresh::NONE
+            }
+        );
+        assert_eq!(
+            snapshot.apply_event(EVENT_OUTPUT, br#"{"change": "unspecified"}"#),
+            Refresh::ALL
+        );
+    }
+
+    #[test]
+    fn workspace_focus_refreshes_window_focus() {
+        let mut snapshot = snapshot();
+        for change in ["focus", "init", "empty"] {
+            let event = format!(
+                r#"{{"change": "{}", "current": {{"id": 10, "type": "workspace"}}}}"#,
+                change
+            );
+            assert_eq!(
+                snapshot.apply_event(EVENT_WORKSPACE, event.as_bytes()),
+                Refresh {
+                    tree: true,
+                    workspaces: true,
+                    ..Refresh::NONE
+                }
+            );
+        }
+
+        // The tree re-queried after focusing an empty workspace has no focused window.
+        let mut tree: SwayNode = serde_json::from_str(TREE).unwrap();
+        tree.nodes[1].nodes[0].nodes[0].focused = false;
+        snapshot.windows = windows_from_tree(&tree);
+        assert_eq!(snapshot.to_state().focused_window_id, None);
+    }
+}
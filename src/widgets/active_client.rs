@@ -1,14 +1,17 @@
-use crate::utils::niri::{self, NiriError, Window};
+use crate::utils::{
+    app_index::{self, AppIndex},
+    compositor,
+    compositor::WindowInfo,
+    icons,
+};
 use glib;
 use gtk4::prelude::*;
-use gtk4::{Align, Box, Label, Orientation, Widget};
+use gtk4::{Align, Box, Image, Label, Orientation, Widget};
 use pango::EllipsizeMode;
-use std::time::Duration;
-
-const UPDATE_INTERVAL: Duration = Duration::from_millis(500);
 
 pub struct ActiveClientWidget {
     container: Box,
+    icon: Image,
     app_id_label: Label,
     title_label: Label,
     max_text_length: usize,
@@ -30,108 +33,137 @@ impl ActiveClientWidget {
             .build();
         title_label.add_css_class("window-title");
 
-        let container = Box::builder()
+        let labels = Box::builder()
             .orientation(Orientation::Vertical)
             .spacing(0)
+            .valign(Align::Center)
+            .build();
+        labels.append(&app_id_label);
+        labels.append(&title_label);
+
+        let icon = Image::builder().pixel_size(24).visible(false).build();
+        icon.add_css_class("app-icon");
+
+        let container = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(8)
             .build();
         container.add_css_class("ActiveClient");
 
-        container.append(&app_id_label);
-        container.append(&title_label);
+        container.append(&icon);
+        container.append(&labels);
 
         let widget = Self {
             container,
+            icon,
             app_id_label,
             title_label,
             max_text_length,
         };
 
-        widget.update_widget_info();
-        widget.schedule_update();
+        widget.subscribe_updates();
 
         widget
     }
 
-    fn truncate_text(&self, text: &str) -> String {
-        if text.chars().count() > self.max_text_length {
-            let mut truncated: String = text.chars().take(self.max_text_length).collect();
-            truncated.push_str("…");
-            truncated
-        } else {
-            text.to_string()
-        }
+    fn subscribe_updates(&self) {
+        let container_weak = self.container.downgrade();
+        let icon_weak = self.icon.downgrade();
+        let app_id_label_weak = self.app_id_label.downgrade();
+        let title_label_weak = self.title_label.downgrade();
+        let max_len = self.max_text_length;
+        let mut state_rx = compositor().subscribe();
+        let mut apps_rx = app_index::subscribe();
+
+        glib::MainContext::default().spawn_local(async move {
+            loop {
+                let (Some(container), Some(icon), Some(app_id_label), Some(title_label)) = (
+                    container_weak.upgrade(),
+                    icon_weak.upgrade(),
+                    app_id_label_weak.upgrade(),
+                    title_label_weak.upgrade(),
+                ) else {
+                    break;
+                };
+                let (connected, focused) = {
+                    let state = state_rx.borrow_and_update();
+                    (state.connected, state.focused_window().cloned())
+                };
+                let apps = apps_rx.borrow_and_update().clone();
+                Self::update_icon(&icon, &apps, focused.as_ref());
+                Self::update_labels(
+                    &container,
+                    &app_id_label,
+                    &title_label,
+                    max_len,
+                    connected,
+                    focused,
+                );
+                drop((container, icon, app_id_label, title_label));
+
+                let changed = tokio::select! {
+                    changed = state_rx.changed() => changed,
+                    changed = apps_rx.changed() => changed,
+                };
+                if changed.is_err() {
+                    break;
+                }
+            }
+        });
     }
 
-    fn update_labels(&self, window_info: Result<Option<Window>, NiriError>) {
-        match window_info {
-            Ok(Some(window)) => {
+    fn update_icon(icon: &Image, apps: &AppIndex, focused: Option<&WindowInfo>) {
+        let Some(app_id) = focused.and_then(|window| window.app_id.as_deref()) else {
+            icon.set_visible(false);
+            return;
+        };
+        let app = apps
+            .as_ref()
+            .and_then(|apps| apps.resolve_by_app_id(app_id));
+        icon.set_from_gicon(&icons::app_icon(app, app_id));
+        icon.set_visible(true);
+    }
+
+    fn update_labels(
+        container: &Box,
+        app_id_label: &Label,
+        title_label: &Label,
+        max_len: usize,
+        connected: bool,
+        focused: Option<WindowInfo>,
+    ) {
+        match focused {
+            Some(window) => {
                 let app_id = window.app_id.unwrap_or_default();
                 let title = window.title.unwrap_or_default();
-                self.app_id_label.set_text(&self.truncate_text(&app_id));
-                self.title_label.set_text(&self.truncate_text(&title));
-                self.container.set_visible(true);
+                app_id_label.set_text(&truncate_text(&app_id, max_len));
+                title_label.set_text(&truncate_text(&title, max_len));
+                container.set_visible(true);
             }
-            Ok(None) => {
-                self.app_id_label.set_text("niri");
-                self.title_label.set_text("Desktop");
-                self.container.set_visible(true);
+            None if connected => {
+                app_id_label.set_text(compositor().name());
+                title_label.set_text("Desktop");
+                container.set_visible(true);
             }
-            Err(_e) => {
-                self.app_id_label.set_text("");
-                self.title_label.set_text("Error");
-                self.container.set_visible(false);
+            None => {
+                app_id_label.set_text("");
+                title_label.set_text("Error");
+                container.set_visible(false);
             }
         }
     }
 
-    fn update_widget_info(&self) {
-        let window_info = niri::get_focused_window();
-        self.update_labels(window_info);
-    }
-
-    fn schedule_update(&self) {
-        let container = self.container.clone();
-        let app_id_label = self.app_id_label.clone();
-        let title_label = self.title_label.clone();
-        let max_len = self.max_text_length;
-
-        glib::timeout_add_local(UPDATE_INTERVAL, move || {
-            let update_result = niri::get_focused_window();
-
-            let truncate = |text: &str| -> String {
-                if text.chars().count() > max_len {
-                    let mut truncated: String = text.chars().take(max_len).collect();
-                    truncated.push_str("…");
-                    truncated
-                } else {
-                    text.to_string()
-                }
-            };
-
-            match update_result {
-                Ok(Some(window)) => {
-                    let app_id = window.app_id.unwrap_or_default();
-                    let title = window.title.unwrap_or_default();
-                    app_id_label.set_text(&truncate(&app_id));
-                    title_label.set_text(&truncate(&title));
-                    container.set_visible(true);
-                }
-                Ok(None) => {
-                    app_id_label.set_text("niri");
-                    title_label.set_text("Desktop");
-                    container.set_visible(true);
-                }
-                Err(_e) => {
-                    app_id_label.set_text("");
-                    title_label.set_text("Error");
-                    container.set_visible(false);
-                }
-            }
-            glib::ControlFlow::Continue
-        });
-    }
-
     pub fn widget(&self) -> &impl IsA<Widget> {
         &self.container
     }
 }
+
+fn truncate_text(text: &str, max_len: usize) -> String {
+    if text.chars().count() > max_len {
+        let mut truncated: String = text.chars().take(max_len).collect();
+        truncated.push('…');
+        truncated
+    } else {
+        text.to_string()
+    }
+}
@@ -2,8 +2,10 @@ mod active_client;
 mod battery;
 mod display_control;
 mod network;
+mod workspaces;
 
 pub use active_client::ActiveClientWidget;
 pub use battery::BatteryWidget;
 pub use display_control::DisplayWidget;
 pub use network::NetworkWidget;
+pub use workspaces::WorkspacesWidget;
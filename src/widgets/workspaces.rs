@@ -0,0 +1,153 @@
+use crate::utils::{compositor, compositor::WorkspaceInfo};
+use gtk4::prelude::*;
+use gtk4::{glib, Box, Button, EventControllerScroll, EventControllerScrollFlags, Orientation};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WorkspaceEntry {
+    id: u64,
+    idx: u32,
+    name: Option<String>,
+    is_active: bool,
+    is_focused: bool,
+    is_urgent: bool,
+}
+
+impl From<&WorkspaceInfo> for WorkspaceEntry {
+    fn from(ws: &WorkspaceInfo) -> Self {
+        Self {
+            id: ws.id,
+            idx: ws.idx,
+            name: ws.name.clone(),
+            is_active: ws.is_active,
+            is_focused: ws.is_focused,
+            is_urgent: ws.is_urgent,
+        }
+    }
+}
+
+type OutputGroup = (String, Vec<WorkspaceEntry>);
+
+pub struct WorkspacesWidget {
+    container: Box,
+}
+
+impl WorkspacesWidget {
+    pub fn new() -> Self {
+        let container = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(0)
+            .build();
+        container.add_css_class("Workspaces");
+
+        let widget = Self { container };
+        widget.subscribe_updates();
+        widget
+    }
+
+    fn subscribe_updates(&self) {
+        let container_weak = self.container.downgrade();
+        let mut state_rx = compositor().subscribe();
+
+        glib::MainContext::default().spawn_local(async move {
+            let mut last_groups: Option<Vec<OutputGroup>> = None;
+            loop {
+                let Some(container) = container_weak.upgrade() else {
+                    break;
+                };
+                let groups: Vec<OutputGroup> = state_rx
+                    .borrow_and_update()
+                    .workspaces_by_output()
+                    .into_iter()
+                    .map(|(output, list)| {
+                        (output, list.into_iter().map(WorkspaceEntry::from).collect())
+                    })
+                    .collect();
+
+                if last_groups.as_ref() != Some(&groups) {
+                    Self::rebuild(&container, &groups);
+                    last_groups = Some(groups);
+                }
+                drop(container);
+
+                if state_rx.changed().await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    fn rebuild(container: &Box, groups: &[OutputGroup]) {
+        while let Some(child) = container.first_child() {
+            container.remove(&child);
+        }
+
+        for (monitor_index, (output, workspaces)) in groups.iter().enumerate() {
+            let group = Box::new(Orientation::Horizontal, 0);
+            group.add_css_class("monitor-workspaces");
+            group.add_css_class(&format!("monitor-{}", monitor_index + 1));
+            if !output.is_empty() {
+                group.set_tooltip_text(Some(output));
+            }
+
+            for ws in workspaces {
+                let button = Button::new();
+                button.add_css_class("workspace-button");
+                button.set_can_focus(false);
+                if ws.is_active {
+                    button.add_css_class("active");
+                }
+                if ws.is_focused {
+                    button.add_css_class("focused");
+                }
+                if ws.is_urgent {
+                    button.add_css_class("urgent");
+                }
+                let tooltip = ws.name.clone().unwrap_or_else(|| ws.idx.to_string());
+                button.set_tooltip_text(Some(&tooltip));
+
+                let id = ws.id;
+                button.connect_clicked(move |_| {
+                    if let Err(e) = compositor().focus_workspace(id) {
+                        eprintln!("Failed to focus workspace {}: {}", id, e);
+                    }
+                });
+                group.append(&button);
+            }
+
+            let ids: Vec<u64> = workspaces.iter().map(|ws| ws.id).collect();
+            let active_pos = workspaces.iter().position(|ws| ws.is_active).unwrap_or(0);
+            let scroll_controller = EventControllerScroll::new(
+                EventControllerScrollFlags::VERTICAL | EventControllerScrollFlags::DISCRETE,
+            );
+            scroll_controller.connect_scroll(move |_, _, dy| {
+                if ids.is_empty() || dy == 0.0 {
+                    return glib::Propagation::Proceed;
+                }
+                let target = if dy > 0.0 {
+                    (active_pos + 1) % ids.len()
+                } else {
+                    (active_pos + ids.len() - 1) % ids.len()
+                };
+                if let Err(e) = compositor().focus_workspace(ids[target]) {
+                    eprintln!("Failed to focus workspace {}: {}", ids[target], e);
+                }
+                glib::Propagation::Stop
+            });
+            group.add_controller(scroll_controller);
+
+            container.append(&group);
+        }
+
+        container.set_visible(!groups.is_empty());
+    }
+
+    pub fn widget(&self) -> &Box {
+        &self.container
+    }
+}
+
+impl Default for WorkspacesWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
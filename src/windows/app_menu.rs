@@ -1,24 +1,69 @@
+use crate::utils::{
+    app_category::MainCategory,
+    app_index,
+    app_resolver::AppInfo,
+    app_usage::{self, AppUsage},
+    config::SearchConfig,
+    icons,
+    launch_feedback::{self, LaunchOrigin},
+    launcher,
+    search::{self, SearchAction, SearchProvider, SearchResult},
+    AppResolver,
+};
 use gtk4::prelude::*;
 use gtk4::{
-    gdk, gio, glib, Align, Box as GtkBox, Button, EventControllerKey, Image, Label, ListBox,
-    Orientation, Popover, ScrolledWindow, SearchEntry, SelectionMode, Spinner,
+    gdk, glib, Align, Box as GtkBox, EventControllerKey, Image, Label, ListBox, ListBoxRow,
+    Orientation, Popover, PropagationPhase, ScrolledWindow, SearchEntry, SelectionMode, Spinner,
+    ToggleButton,
+};
+use pango::EllipsizeMode;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+    sync::Arc,
 };
-use std::{cell::Cell, cell::RefCell, rc::Rc};
+use tokio::sync::watch;
+
+/// How many frequently used apps lead the list while the search is empty.
+const FREQUENT_LIMIT: usize = 6;
+/// Scales usage into search scores so it breaks ties without drowning out text matches.
+const USAGE_WEIGHT: f64 = 12.0;
+
+struct AppRow {
+    app: AppInfo,
+    row: ListBoxRow,
+}
 
-type AppInfoData = (String, Option<String>, String);
-type AppInfoEntry = (String, Option<gio::Icon>, String);
+/// Position of each row in the current ranking; rows not in it are filtered out.
+type Ranking = Rc<RefCell<HashMap<ListBoxRow, usize>>>;
+/// Section titles keyed by the first row of each section.
+type Headers = Rc<RefCell<HashMap<ListBoxRow, &'static str>>>;
+type Section<'a> = (&'static str, Vec<&'a ListBoxRow>);
 
 pub struct AppMenu {
     popover: Popover,
     list_box: ListBox,
+    scroll: ScrolledWindow,
     search_entry: SearchEntry,
+    category_bar: GtkBox,
+    /// The category being browsed; `None` shows every app.
+    category: Cell<Option<MainCategory>>,
     spinner: Spinner,
-    all_apps: Rc<RefCell<Option<Vec<AppInfoEntry>>>>,
+    index: RefCell<Option<Arc<AppResolver>>>,
+    rows: RefCell<Vec<AppRow>>,
+    ranking: Ranking,
+    headers: Headers,
+    providers: Vec<Box<dyn SearchProvider>>,
+    max_results: usize,
+    /// Rows for the current provider results, rebuilt on every query.
+    result_rows: RefCell<Vec<(ListBoxRow, SearchAction)>>,
+    usage_rx: watch::Receiver<AppUsage>,
     apps_loaded: Rc<Cell<bool>>,
 }
 
 impl AppMenu {
-    pub fn new() -> Rc<Self> {
+    pub fn new(config: &SearchConfig) -> Rc<Self> {
         let popover = Popover::new();
         popover.add_css_class("AppMenuPopover");
 
@@ -29,6 +74,14 @@ impl AppMenu {
         search_entry.add_css_class("app-menu-search");
         container.append(&search_entry);
 
+        let category_bar = GtkBox::new(Orientation::Horizontal, 4);
+        category_bar.add_css_class("app-menu-categories");
+        let category_scroll = ScrolledWindow::new();
+        category_scroll.set_policy(gtk4::PolicyType::Automatic, gtk4::PolicyType::Never);
+        category_scroll.add_css_class("app-menu-categories-scroll");
+        category_scroll.set_child(Some(&category_bar));
+        container.append(&category_scroll);
+
         let scroll = ScrolledWindow::new();
         scroll.set_min_content_height(400);
         scroll.set_min_content_width(300);
@@ -37,7 +90,23 @@ impl AppMenu {
 
         let list_box = ListBox::new();
         list_box.add_css_class("app-menu-list");
-        list_box.set_selection_mode(SelectionMode::None);
+        list_box.set_selection_mode(SelectionMode::Single);
+        list_box.set_activate_on_single_click(true);
+
+        let ranking: Ranking = Rc::new(RefCell::new(HashMap::new()));
+        let filter_ranking = ranking.clone();
+        list_box.set_filter_func(move |row| filter_ranking.borrow().contains_key(row));
+        let sort_ranking = ranking.clone();
+        list_box.set_sort_func(move |a, b| {
+            let ranking = sort_ranking.borrow();
+            ranking.get(a).cmp(&ranking.get(b)).into()
+        });
+        let headers: Headers = Rc::new(RefCell::new(HashMap::new()));
+        let header_titles = headers.clone();
+        list_box.set_header_func(move |row, _| {
+            let title = header_titles.borrow().get(row).copied();
+            row.set_header(title.map(Self::create_section_header).as_ref());
+        });
 
         let spinner = Spinner::builder()
             .spinning(true)
@@ -57,188 +126,235 @@ impl AppMenu {
 
         popover.set_child(Some(&container));
 
-        let key_controller = EventControllerKey::new();
-        let popover_weak = popover.downgrade();
-        key_controller.connect_key_pressed(move |_, keyval, _, _| {
-            if keyval == gdk::Key::Escape {
-                if let Some(popover) = popover_weak.upgrade() {
-                    if popover.is_visible() {
-                        popover.popdown();
-                        return glib::Propagation::Stop;
-                    }
-                }
-            }
-            glib::Propagation::Proceed
-        });
-        search_entry.add_controller(key_controller);
-
         let menu = Rc::new(Self {
             popover,
             list_box,
+            scroll,
             search_entry,
+            category_bar,
+            category: Cell::new(None),
             spinner,
-            all_apps: Rc::new(RefCell::new(None)),
+            index: RefCell::new(None),
+            rows: RefCell::new(Vec::new()),
+            ranking,
+            headers,
+            providers: search::providers(config),
+            max_results: config.max_results,
+            result_rows: RefCell::new(Vec::new()),
+            usage_rx: app_usage::subscribe(),
             apps_loaded: Rc::new(Cell::new(false)),
         });
 
+        menu.connect_keys();
         menu.connect_search();
         menu.connect_popover_visibility();
+        menu.watch_apps();
+        menu.watch_usage();
 
         menu
     }
 
-    fn load_applications_async(self: Rc<Self>) {
-        self.spinner.set_visible(true);
-        self.spinner.start();
-        self.list_box.set_visible(false);
+    fn watch_usage(self: &Rc<Self>) {
+        let mut usage_rx = self.usage_rx.clone();
+        let menu_weak = Rc::downgrade(self);
 
-        let list_box_clone = self.list_box.clone();
-        let spinner_clone = self.spinner.clone();
-        let all_apps_clone = self.all_apps.clone();
-        let apps_loaded_clone = self.apps_loaded.clone();
-        let search_entry_clone = self.search_entry.clone();
-        let self_clone = self.clone();
+        glib::MainContext::default().spawn_local(async move {
+            while usage_rx.changed().await.is_ok() {
+                let Some(menu) = menu_weak.upgrade() else {
+                    break;
+                };
+                menu.filter_applications();
+            }
+        });
+    }
+
+    fn watch_apps(self: &Rc<Self>) {
+        let mut apps_rx = app_index::subscribe();
+        let menu_weak = Rc::downgrade(self);
 
         glib::MainContext::default().spawn_local(async move {
-            let app_data_result = tokio::task::spawn_blocking(Self::load_applications_sync).await;
-
-            match app_data_result {
-                Ok(app_data) => {
-                    let mut final_apps: Vec<AppInfoEntry> = Vec::with_capacity(app_data.len());
-                    for (name, icon_name_opt, exec) in app_data {
-                        let icon = icon_name_opt
-                            .and_then(|icon_name| gio::Icon::for_string(&icon_name).ok());
-                        final_apps.push((name, icon, exec));
-                    }
-
-                    *all_apps_clone.borrow_mut() = Some(final_apps);
-                    apps_loaded_clone.set(true);
-
-                    Self::populate_list_box_static(
-                        &self_clone,
-                        &list_box_clone,
-                        &all_apps_clone.borrow().as_ref().unwrap(),
-                    );
-                    Self::filter_applications_static(
-                        &self_clone,
-                        &list_box_clone,
-                        &all_apps_clone.borrow().as_ref().unwrap(),
-                        &search_entry_clone.text(),
-                    );
+            loop {
+                let index = apps_rx.borrow_and_update().clone();
+                let Some(menu) = menu_weak.upgrade() else {
+                    break;
+                };
+                if let Some(index) = index {
+                    menu.set_apps(index);
                 }
-                Err(e) => {
-                    eprintln!("Failed to load applications in background task: {}", e);
-                    *all_apps_clone.borrow_mut() = Some(Vec::new());
-                    apps_loaded_clone.set(true);
+                drop(menu);
+
+                if apps_rx.changed().await.is_err() {
+                    break;
                 }
             }
-
-            spinner_clone.stop();
-            spinner_clone.set_visible(false);
-            list_box_clone.set_visible(true);
         });
     }
 
-    fn load_applications_sync() -> Vec<AppInfoData> {
-        let mut apps: Vec<AppInfoData> = gio::AppInfo::all()
-            .into_iter()
-            .filter(gio::AppInfo::should_show)
-            .filter_map(|app_info| {
-                let name = app_info.name().to_string();
-                let icon_name = app_info
-                    .icon()
-                    .and_then(|i| i.to_string())
-                    .map(|gs| gs.to_string());
-                let exec = app_info.commandline()?.to_string_lossy().into_owned();
-
-                if !name.is_empty() && !exec.is_empty() {
-                    Some((name, icon_name, exec))
-                } else {
-                    None
-                }
-            })
+    fn set_apps(self: &Rc<Self>, index: Arc<AppResolver>) {
+        let mut apps: Vec<&AppInfo> = index
+            .apps()
+            .filter(|app| !app.name.is_empty() && !app.exec.is_empty())
             .collect();
+        apps.sort_by_cached_key(|app| app.name.to_lowercase());
+
+        let mut rows = self.rows.borrow_mut();
+        for old in rows.drain(..) {
+            self.list_box.remove(&old.row);
+        }
+        for app in apps {
+            let row = Self::create_app_row(app);
+            self.list_box.append(&row);
+            rows.push(AppRow {
+                app: app.clone(),
+                row,
+            });
+        }
+        drop(rows);
+        self.rebuild_categories();
+
+        *self.index.borrow_mut() = Some(index);
+        self.apps_loaded.set(true);
 
-        apps.sort_by(|a, b| a.0.to_lowercase().cmp(&b.0.to_lowercase()));
-        apps
+        self.spinner.stop();
+        self.spinner.set_visible(false);
+        self.list_box.set_visible(true);
+
+        self.filter_applications();
     }
 
-    fn create_app_row(
-        app_menu: &Rc<Self>,
-        name: &str,
-        icon: &Option<gio::Icon>,
-        exec: &str,
-    ) -> Button {
-        let row = GtkBox::new(Orientation::Horizontal, 12);
-        row.add_css_class("app-menu-item-box");
-
-        let image = match icon {
-            Some(gicon) => Image::from_gicon(gicon),
-            None => {
-                let fallback_icon = gio::Icon::for_string("application-x-executable")
-                    .expect("Failed to create fallback gio::Icon");
-                Image::from_gicon(&fallback_icon)
-            }
-        };
+    fn create_app_row(app: &AppInfo) -> ListBoxRow {
+        let row_box = GtkBox::new(Orientation::Horizontal, 12);
+        row_box.add_css_class("app-menu-item-box");
+
+        let image = Image::from_gicon(&icons::app_icon(Some(app), &app.desktop_id));
         image.add_css_class("app-menu-item-icon");
         image.set_pixel_size(32);
-        row.append(&image);
+        row_box.append(&image);
 
-        let label = Label::new(Some(name));
+        let label = Label::new(Some(&app.name));
         label.add_css_class("app-menu-item-label");
         label.set_halign(Align::Start);
         label.set_hexpand(true);
-        row.append(&label);
-
-        let button = Button::new();
-        button.add_css_class("app-menu-item");
-        button.set_child(Some(&row));
-
-        let exec = exec.to_string();
-        let name = name.to_string();
-        let popover_weak = app_menu.popover.downgrade();
-        button.connect_clicked(move |_| {
-            if let Some(popover) = popover_weak.upgrade() {
-                popover.popdown();
+        row_box.append(&label);
+
+        let row = ListBoxRow::new();
+        row.add_css_class("app-menu-item");
+        row.set_child(Some(&row_box));
+        row
+    }
+
+    fn rebuild_categories(self: &Rc<Self>) {
+        while let Some(child) = self.category_bar.first_child() {
+            self.category_bar.remove(&child);
+        }
+
+        let rows = self.rows.borrow();
+        let present: Vec<MainCategory> = MainCategory::ALL
+            .into_iter()
+            .filter(|category| rows.iter().any(|row| row.app.in_category(*category)))
+            .collect();
+        drop(rows);
+        if !self
+            .category
+            .get()
+            .is_some_and(|category| present.contains(&category))
+        {
+            self.category.set(None);
+        }
+
+        let all = self.add_category_chip(None, None);
+        for category in present {
+            self.add_category_chip(Some(category), Some(&all));
+        }
+    }
+
+    fn add_category_chip(
+        self: &Rc<Self>,
+        category: Option<MainCategory>,
+        group: Option<&ToggleButton>,
+    ) -> ToggleButton {
+        let (label, icon_name) = match category {
+            Some(category) => (category.label(), category.icon_name()),
+            None => ("All", "view-app-grid-symbolic"),
+        };
+        let content = GtkBox::new(Orientation::Horizontal, 4);
+        content.append(&Image::from_icon_name(icon_name));
+        content.append(&Label::new(Some(label)));
+
+        let chip = ToggleButton::builder()
+            .child(&content)
+            .can_focus(false)
+            .active(self.category.get() == category)
+            .build();
+        chip.add_css_class("app-menu-category");
+        chip.set_group(group);
+
+        let menu_weak = Rc::downgrade(self);
+        chip.connect_toggled(move |chip| {
+            if !chip.is_active() {
+                return;
             }
-            match gio::AppInfo::create_from_commandline(
-                &exec,
-                Some(&name),
-                gio::AppInfoCreateFlags::NONE,
-            ) {
-                Ok(app_info) => {
-                    if let Err(e) = app_info.launch(&[], None::<&gio::AppLaunchContext>) {
-                        eprintln!("Failed to launch '{}' ({}): {}", name, exec, e);
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Failed to create AppInfo for '{}' ({}): {}", name, exec, e);
-                }
+            if let Some(menu) = menu_weak.upgrade() {
+                menu.category.set(category);
+                menu.filter_applications();
             }
         });
-
-        button
+        self.category_bar.append(&chip);
+        chip
     }
 
-    fn populate_list_box_static(app_menu: &Rc<Self>, list_box: &ListBox, apps: &[AppInfoEntry]) {
-        while let Some(child) = list_box.first_child() {
-            list_box.remove(&child);
-        }
-        for (name, icon, exec) in apps {
-            let row = Self::create_app_row(app_menu, name, icon, exec);
-            list_box.append(&row);
+    fn create_result_row(result: &SearchResult) -> ListBoxRow {
+        let row_box = GtkBox::new(Orientation::Horizontal, 12);
+        row_box.add_css_class("app-menu-item-box");
+
+        let image = Image::from_gicon(&result.icon);
+        image.add_css_class("app-menu-item-icon");
+        image.set_pixel_size(32);
+        row_box.append(&image);
+
+        let labels = GtkBox::new(Orientation::Vertical, 0);
+        labels.set_valign(Align::Center);
+        labels.set_hexpand(true);
+        let title = Label::new(Some(&result.title));
+        title.add_css_class("app-menu-item-label");
+        title.set_halign(Align::Start);
+        title.set_ellipsize(EllipsizeMode::End);
+        labels.append(&title);
+        if let Some(subtitle) = &result.subtitle {
+            let subtitle = Label::new(Some(subtitle));
+            subtitle.add_css_class("app-menu-item-subtitle");
+            subtitle.set_halign(Align::Start);
+            subtitle.set_ellipsize(EllipsizeMode::Middle);
+            labels.append(&subtitle);
         }
+        row_box.append(&labels);
+
+        let row = ListBoxRow::new();
+        row.add_css_class("app-menu-item");
+        row.set_child(Some(&row_box));
+        row
+    }
+
+    fn create_section_header(title: &str) -> Label {
+        let label = Label::new(Some(title));
+        label.add_css_class("app-menu-section");
+        label.set_halign(Align::Start);
+        label
     }
 
     fn connect_popover_visibility(self: &Rc<Self>) {
-        let self_clone = self.clone();
+        let apps_loaded = self.apps_loaded.clone();
+        let spinner = self.spinner.clone();
+        let list_box = self.list_box.clone();
         let search_entry_clone = self.search_entry.clone();
         self.popover.connect_visible_notify(move |popover| {
             if popover.is_visible() {
                 search_entry_clone.set_can_focus(true);
                 search_entry_clone.grab_focus();
-                if !self_clone.apps_loaded.get() {
-                    self_clone.clone().load_applications_async();
+                if !apps_loaded.get() {
+                    spinner.set_visible(true);
+                    spinner.start();
+                    list_box.set_visible(false);
                 }
             } else {
                 search_entry_clone.set_text("");
@@ -247,40 +363,269 @@ impl AppMenu {
     }
 
     fn connect_search(self: &Rc<Self>) {
-        let list_box_clone = self.list_box.clone();
-        let apps_rc_clone = self.all_apps.clone();
-        let apps_loaded_clone = self.apps_loaded.clone();
-        let self_clone = self.clone();
+        let menu_weak = Rc::downgrade(self);
+        self.search_entry.connect_search_changed(move |_| {
+            if let Some(menu) = menu_weak.upgrade() {
+                menu.filter_applications();
+            }
+        });
 
-        self.search_entry.connect_search_changed(move |entry| {
-            if !apps_loaded_clone.get() {
-                return;
+        let menu_weak = Rc::downgrade(self);
+        self.search_entry.connect_activate(move |_| {
+            if let Some(menu) = menu_weak.upgrade() {
+                menu.launch_selected();
             }
-            let text = entry.text();
-            let apps_borrow = apps_rc_clone.borrow();
-            if let Some(apps) = apps_borrow.as_ref() {
-                Self::filter_applications_static(&self_clone, &list_box_clone, apps, &text);
+        });
+
+        let menu_weak = Rc::downgrade(self);
+        self.list_box.connect_row_activated(move |_, row| {
+            if let Some(menu) = menu_weak.upgrade() {
+                menu.launch_row(row);
             }
         });
     }
 
-    fn filter_applications_static(
-        app_menu: &Rc<Self>,
-        list_box: &ListBox,
-        apps: &[AppInfoEntry],
-        search: &str,
-    ) {
-        while let Some(child) = list_box.first_child() {
-            list_box.remove(&child);
+    fn connect_keys(self: &Rc<Self>) {
+        let key_controller = EventControllerKey::new();
+        key_controller.set_propagation_phase(PropagationPhase::Capture);
+        let menu_weak = Rc::downgrade(self);
+        key_controller.connect_key_pressed(move |_, keyval, _, _| {
+            let Some(menu) = menu_weak.upgrade() else {
+                return glib::Propagation::Proceed;
+            };
+            match keyval {
+                gdk::Key::Escape if menu.popover.is_visible() => menu.popover.popdown(),
+                gdk::Key::Down => menu.move_selection(1),
+                gdk::Key::Up => menu.move_selection(-1),
+                _ => return glib::Propagation::Proceed,
+            }
+            glib::Propagation::Stop
+        });
+        self.search_entry.add_controller(key_controller);
+    }
+
+    fn filter_applications(&self) {
+        if !self.apps_loaded.get() {
+            return;
         }
+        let text = self.search_entry.text();
+        let (only, query) = search::route(&self.providers, text.trim_start());
+        let query = query.trim();
 
-        let search_lower = search.to_lowercase();
-        for (name, icon, exec) in apps.iter() {
-            if search_lower.is_empty() || name.to_lowercase().contains(&search_lower) {
-                let row = Self::create_app_row(app_menu, name, icon, exec);
-                list_box.append(&row);
+        let mut result_rows = self.result_rows.borrow_mut();
+        for (row, _) in result_rows.drain(..) {
+            self.list_box.remove(&row);
+        }
+        let mut before = Vec::new();
+        let mut after = Vec::new();
+        if only.is_some() || !query.is_empty() {
+            for (i, provider) in self.providers.iter().enumerate() {
+                let wanted = match only {
+                    Some(only) => only == i,
+                    None => provider.answers_unprefixed(),
+                };
+                if !wanted {
+                    continue;
+                }
+                let results = provider.search(query, self.max_results);
+                if results.is_empty() {
+                    continue;
+                }
+                let first = result_rows.len();
+                for result in results {
+                    let row = Self::create_result_row(&result);
+                    self.list_box.append(&row);
+                    result_rows.push((row, result.action));
+                }
+                let section = (provider.title(), first..result_rows.len());
+                if provider.before_apps() {
+                    before.push(section);
+                } else {
+                    after.push(section);
+                }
             }
         }
+
+        let rows = self.rows.borrow();
+        let result_section = |(title, range): (&'static str, std::ops::Range<usize>)| {
+            (
+                title,
+                result_rows[range].iter().map(|(row, _)| row).collect(),
+            )
+        };
+        let mut sections: Vec<Section> = before.into_iter().map(result_section).collect();
+        if only.is_none() {
+            sections.extend(self.app_sections(&rows, query));
+        }
+        sections.extend(after.into_iter().map(result_section));
+        sections.retain(|(_, rows)| !rows.is_empty());
+
+        let ranked: Vec<&ListBoxRow> = sections
+            .iter()
+            .flat_map(|(_, rows)| rows.iter().copied())
+            .collect();
+        *self.ranking.borrow_mut() = ranked
+            .iter()
+            .enumerate()
+            .map(|(position, row)| ((*row).clone(), position))
+            .collect();
+        // A lone section needs no title.
+        *self.headers.borrow_mut() = if sections.len() > 1 {
+            sections
+                .iter()
+                .map(|(title, rows)| (rows[0].clone(), *title))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        self.list_box.invalidate_filter();
+        self.list_box.invalidate_sort();
+        self.list_box.invalidate_headers();
+
+        let first = ranked.first().copied();
+        self.list_box.select_row(first);
+        if first.is_some() {
+            self.scroll.vadjustment().set_value(0.0);
+        }
+    }
+
+    fn app_sections<'a>(&self, rows: &'a [AppRow], query: &str) -> Vec<Section<'a>> {
+        let index = self.index.borrow();
+        let usage = self.usage_rx.borrow();
+        let category = self.category.get();
+        let in_category = |row: &&AppRow| category.map_or(true, |c| row.app.in_category(c));
+
+        match index.as_deref() {
+            Some(index) if !query.is_empty() => {
+                let mut scored: Vec<(i32, &AppRow)> = rows
+                    .iter()
+                    .filter(in_category)
+                    .filter_map(|row| {
+                        let score = index.match_score(query, &row.app)?;
+                        let usage = usage.score(&row.app.desktop_id).ln_1p() * USAGE_WEIGHT;
+                        Some((score + usage as i32, row))
+                    })
+                    .collect();
+                // Stable, so equal scores keep alphabetical order.
+                scored.sort_by(|a, b| b.0.cmp(&a.0));
+                let ranked = scored.into_iter().map(|(_, row)| &row.row).collect();
+                vec![("Applications", ranked)]
+            }
+            _ if category.is_some() => {
+                let ranked = rows
+                    .iter()
+                    .filter(in_category)
+                    .map(|row| &row.row)
+                    .collect();
+                vec![("Applications", ranked)]
+            }
+            _ => {
+                let frequent: Vec<&AppRow> = usage
+                    .most_used()
+                    .into_iter()
+                    .filter_map(|id| {
+                        rows.iter()
+                            .find(|row| row.app.desktop_id.eq_ignore_ascii_case(id))
+                    })
+                    .take(FREQUENT_LIMIT)
+                    .collect();
+                let rest = rows
+                    .iter()
+                    .filter(|row| !frequent.iter().any(|f| std::ptr::eq(*f, *row)))
+                    .map(|row| &row.row)
+                    .collect();
+                vec![
+                    ("Frequent", frequent.iter().map(|row| &row.row).collect()),
+                    ("All applications", rest),
+                ]
+            }
+        }
+    }
+
+    fn visible_rows(&self) -> Vec<ListBoxRow> {
+        let ranking = self.ranking.borrow();
+        let mut rows: Vec<(&ListBoxRow, &usize)> = ranking.iter().collect();
+        rows.sort_by_key(|(_, position)| **position);
+        rows.into_iter().map(|(row, _)| row.clone()).collect()
+    }
+
+    fn move_selection(&self, step: i32) {
+        let rows = self.visible_rows();
+        if rows.is_empty() {
+            return;
+        }
+        let len = rows.len() as i32;
+        let current = self
+            .list_box
+            .selected_row()
+            .and_then(|selected| rows.iter().position(|row| *row == selected))
+            .map(|index| index as i32)
+            .unwrap_or(if step > 0 { -1 } else { len });
+        let row = &rows[(current + step).rem_euclid(len) as usize];
+
+        self.list_box.select_row(Some(row));
+        if let Some(bounds) = row.compute_bounds(&self.list_box) {
+            let top = bounds.y() as f64;
+            self.scroll
+                .vadjustment()
+                .clamp_page(top, top + bounds.height() as f64);
+        }
+    }
+
+    fn launch_selected(&self) {
+        if let Some(row) = self.list_box.selected_row() {
+            self.launch_row(&row);
+        }
+    }
+
+    fn launch_row(&self, row: &ListBoxRow) {
+        let action = self
+            .result_rows
+            .borrow()
+            .iter()
+            .find(|(result_row, _)| result_row == row)
+            .map(|(_, action)| action.clone());
+        if let Some(action) = action {
+            self.popover.popdown();
+            action.activate();
+            return;
+        }
+
+        let rows = self.rows.borrow();
+        let Some(app) = rows.iter().find(|r| r.row == *row).map(|r| &r.app) else {
+            return;
+        };
+        let app = app.clone();
+        drop(rows);
+        self.popover.popdown();
+        match launcher::launch(&app, None) {
+            Ok(()) => {
+                app_usage::record_launch(&app.desktop_id);
+                launch_feedback::track(app.app_ids.clone(), LaunchOrigin::AppMenu);
+            }
+            Err(e) => eprintln!("Failed to launch '{}' ({}): {}", app.name, app.exec, e),
+        }
+    }
+
+    pub fn show_launch_feedback(&self, widget: &impl IsA<gtk4::Widget>) {
+        let mut launches_rx = launch_feedback::subscribe();
+        let widget_weak = widget.as_ref().downgrade();
+        glib::MainContext::default().spawn_local(async move {
+            while launches_rx.changed().await.is_ok() {
+                let launching = launches_rx
+                    .borrow_and_update()
+                    .values()
+                    .any(|launch| launch.origin == LaunchOrigin::AppMenu);
+                let Some(widget) = widget_weak.upgrade() else {
+                    break;
+                };
+                if launching {
+                    widget.add_css_class("launching");
+                } else {
+                    widget.remove_css_class("launching");
+                }
+            }
+        });
     }
 
     pub fn popover(&self) -> &Popover {
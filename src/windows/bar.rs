@@ -4,14 +4,19 @@ use crate::utils::{
     get_distro_icon_name,
     network::NetworkCommand,
 };
-use crate::widgets::{ActiveClientWidget, BatteryWidget, DisplayWidget, NetworkWidget};
-use crate::windows::{AppMenu, BatteryWindow, DateWindow, DisplayControlWindow, NetworkWindow};
+use crate::widgets::{
+    ActiveClientWidget, BatteryWidget, DisplayWidget, NetworkWidget, WorkspacesWidget,
+};
+use crate::windows::{
+    AppMenu, BatteryWindow, DateWindow, DisplayControlWindow, NetworkWindow, PowerMenu,
+};
 use chrono::Local;
 use glib::source::timeout_add_local;
 use glib::ControlFlow;
 use gtk4::prelude::*;
 use gtk4::{
-    glib, Application, ApplicationWindow, Box as GtkBox, Button, Label, MenuButton, Orientation,
+    gdk, glib, Application, ApplicationWindow, Box as GtkBox, Button, Label, MenuButton,
+    Orientation,
 };
 use gtk4_layer_shell::{Edge, KeyboardMode, Layer, LayerShell};
 use std::{cell::RefCell, rc::Rc, time::Duration};
@@ -19,18 +24,20 @@ use tokio::sync::mpsc;
 
 pub struct BarWindow {
     pub window: ApplicationWindow,
-    _date_popover_provider: DateWindow,
+    date_window: DateWindow,
     _app_menu: Option<Rc<AppMenu>>,
-    _battery_window: Option<Rc<BatteryWindow>>,
+    battery_window: Option<Rc<BatteryWindow>>,
     pub network_widget: Option<Rc<NetworkWidget>>,
     pub network_window: Option<Rc<NetworkWindow>>,
-    _display_window: Option<Rc<DisplayControlWindow>>,
+    display_window: Option<Rc<DisplayControlWindow>>,
+    _power_menu: Option<Rc<PowerMenu>>,
 }
 
 impl BarWindow {
     pub fn new(
         app: &Application,
         config: &BarConfig,
+        monitor: &gdk::Monitor,
         net_command_tx: mpsc::Sender<NetworkCommand>,
         network_service_available: bool,
     ) -> Self {
@@ -41,6 +48,7 @@ impl BarWindow {
         let window = ApplicationWindow::builder().application(app).build();
         window.add_css_class("Bar");
         window.init_layer_shell();
+        window.set_monitor(Some(monitor));
         window.set_layer(Layer::Top);
         window.auto_exclusive_zone_enable();
         window.set_anchor(Edge::Top, true);
@@ -65,6 +73,7 @@ impl BarWindow {
         let mut network_widget_instance: Option<Rc<NetworkWidget>> = None;
         let mut network_window_instance: Option<Rc<NetworkWindow>> = None;
         let mut display_window_instance: Option<Rc<DisplayControlWindow>> = None;
+        let mut power_menu_instance: Option<Rc<PowerMenu>> = None;
 
         let fmt = config
             .clock_format
@@ -102,8 +111,9 @@ impl BarWindow {
                         .unwrap_or_else(|| "open-menu-symbolic".to_string());
                     btn.set_icon_name(&icon);
 
-                    let menu = AppMenu::new();
+                    let menu = AppMenu::new(&cfg.search);
                     btn.set_popover(Some(menu.popover()));
+                    menu.show_launch_feedback(&btn);
 
                     let popover = menu.popover().clone();
                     let window_weak_clone_show = window_weak.clone();
@@ -136,7 +146,7 @@ impl BarWindow {
                     lbl.set_text(&now.format(&fmt).to_string());
                     clock_button.set_child(Some(&lbl));
 
-                    let lbl_clone = lbl.clone();
+                    let lbl_weak = lbl.downgrade();
                     let fmt_clone = fmt.clone();
                     let update_interval = if fmt_clone.contains("%S") {
                         Duration::from_secs(1)
@@ -145,9 +155,12 @@ impl BarWindow {
                     };
 
                     timeout_add_local(update_interval, move || {
+                        let Some(lbl) = lbl_weak.upgrade() else {
+                            return ControlFlow::Break;
+                        };
                         let now = Local::now();
                         let time_str = now.format(&fmt_clone).to_string();
-                        lbl_clone.set_label(&time_str);
+                        lbl.set_label(&time_str);
                         ControlFlow::Continue
                     });
 
@@ -224,6 +237,21 @@ impl BarWindow {
                     display_window_instance = Some(display_widget_instance_struct.window().clone());
                     target.append(display_widget_instance_struct.widget());
                 }
+                ModuleType::Workspaces => {
+                    let w = WorkspacesWidget::new();
+                    target.append(w.widget());
+                }
+                ModuleType::Power => {
+                    let btn = MenuButton::new();
+                    btn.add_css_class("power-menu-button");
+                    btn.set_icon_name("system-shutdown-symbolic");
+
+                    let menu = PowerMenu::new();
+                    btn.set_popover(Some(menu.popover()));
+
+                    power_menu_instance = Some(menu);
+                    target.append(&btn);
+                }
 
             }
         };
@@ -245,16 +273,32 @@ impl BarWindow {
 
         BarWindow {
             window,
-            _date_popover_provider: date_window_instance,
+            date_window: date_window_instance,
             _app_menu: app_menu_instance,
-            _battery_window: battery_window_instance,
+            battery_window: battery_window_instance,
             network_widget: network_widget_instance,
             network_window: network_window_instance,
-            _display_window: display_window_instance,
+            display_window: display_window_instance,
+            _power_menu: power_menu_instance,
         }
     }
 
     pub fn present(&self) {
         self.window.present();
     }
+
+    pub fn close(&self) {
+        let popovers = [
+            Some(self.date_window.popover().clone()),
+            self.battery_window.as_ref().map(|w| w.popover().clone()),
+            self.network_window.as_ref().map(|w| w.popover().clone()),
+            self.display_window.as_ref().map(|w| w.popover().clone()),
+        ];
+        for popover in popovers.into_iter().flatten() {
+            if popover.parent().is_some() {
+                popover.unparent();
+            }
+        }
+        self.window.close();
+    }
 }
@@ -1,38 +1,121 @@
-use crate::utils::{config::DockConfig, AppResolver};
-use crate::utils::niri;
+use crate::utils::{
+    app_index::{self, AppIndex},
+    app_resolver::{AppInfo, DesktopAction},
+    app_usage,
+    compositor,
+    compositor::{CompositorState, WindowGeometry, WindowInfo},
+    config::{DockConfig, DockPosition},
+    dock_favorites::{self, Favorites},
+    icons,
+    launch_feedback::{self, LaunchOrigin, PendingLaunches},
+    launcher,
+    launcher_entry::{self, LauncherEntries},
+};
 use gtk4::prelude::*;
-use gtk4::{glib, Application, ApplicationWindow, Box as GtkBox, Button, EventControllerMotion, Image, Orientation};
+use gtk4::{gdk, gio, glib, Application, ApplicationWindow, Box as GtkBox, Button, DragSource, DropTarget, EventControllerMotion, GestureClick, Image, Label, Orientation, Overlay, Popover, PositionType, ProgressBar, Separator, WidgetPaintable};
 use gtk4_layer_shell::{Edge, KeyboardMode, Layer, LayerShell};
 use std::{cell::RefCell, rc::Rc, time::Duration};
+use tokio::sync::watch;
+
+const TRIGGER_SIZE: i32 = 5;
+const DOCK_PADDING: f64 = 24.0;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct RunningApps {
+    app_ids: Vec<String>,
+    focused: Option<String>,
+}
+
+impl RunningApps {
+    fn from_state(state: &CompositorState) -> Self {
+        let mut windows: Vec<&WindowInfo> = state.windows.values().collect();
+        windows.sort_by_key(|w| w.id);
+
+        let mut app_ids: Vec<String> = Vec::new();
+        for app_id in windows.iter().filter_map(|w| w.app_id.as_ref()) {
+            let app_id = app_id.to_lowercase();
+            if !app_ids.contains(&app_id) {
+                app_ids.push(app_id);
+            }
+        }
+
+        Self {
+            app_ids,
+            focused: state
+                .focused_window()
+                .and_then(|w| w.app_id.as_ref())
+                .map(|id| id.to_lowercase()),
+        }
+    }
+}
+
+struct DockApp {
+    pin_id: String,
+    desktop_id: String,
+    name: String,
+    app_ids: Vec<String>,
+    info: Option<AppInfo>,
+}
+
+impl DockApp {
+    fn actions(&self) -> &[DesktopAction] {
+        self.info.as_ref().map_or(&[], |info| info.actions.as_slice())
+    }
+}
+
+struct DockItem {
+    app: Rc<DockApp>,
+    pinned: bool,
+    button: Button,
+    indicator: GtkBox,
+    badge: Label,
+    progress: ProgressBar,
+}
 
 pub struct DockWindow {
     trigger_window: ApplicationWindow,
     dock_window: Rc<RefCell<Option<ApplicationWindow>>>,
+    container: RefCell<Option<GtkBox>>,
+    items: RefCell<Vec<DockItem>>,
+    running: RefCell<RunningApps>,
+    state_rx: watch::Receiver<CompositorState>,
+    entries_rx: watch::Receiver<LauncherEntries>,
+    launches_rx: watch::Receiver<PendingLaunches>,
+    apps_rx: watch::Receiver<AppIndex>,
+    favorites_rx: watch::Receiver<Favorites>,
     app: Application,
+    monitor: gdk::Monitor,
     config: DockConfig,
     hide_timer: Rc<RefCell<Option<glib::SourceId>>>,
     trigger_hover: Rc<RefCell<bool>>,
     dock_hover: Rc<RefCell<bool>>,
+    hold_open: Rc<RefCell<bool>>,
+    obstructed: RefCell<bool>,
+    dock_size: RefCell<Option<(i32, i32)>>,
 }
 
-
-
 impl DockWindow {
-    pub fn new(app: &Application, config: &DockConfig) -> Rc<Self> {
+    pub fn new(app: &Application, config: &DockConfig, monitor: &gdk::Monitor) -> Rc<Self> {
         let trigger_window = ApplicationWindow::builder()
             .application(app)
             .build();
 
         trigger_window.init_layer_shell();
+        trigger_window.set_monitor(Some(monitor));
         trigger_window.set_layer(Layer::Top);
         trigger_window.set_namespace(Some("kaneru-dock-trigger"));
         trigger_window.set_keyboard_mode(KeyboardMode::None);
 
         Self::setup_trigger_positioning(&trigger_window, config);
 
-        let trigger_area = GtkBox::new(Orientation::Horizontal, 0);
-        trigger_area.set_height_request(5);
-        trigger_area.set_hexpand(true);
+        let trigger_area = GtkBox::new(Self::orientation(config), 0);
+        if config.position == DockPosition::Bottom {
+            trigger_area.set_height_request(TRIGGER_SIZE);
+            trigger_area.set_hexpand(true);
+        } else {
+            trigger_area.set_width_request(TRIGGER_SIZE);
+            trigger_area.set_vexpand(true);
+        }
         trigger_area.add_css_class("dock-trigger");
 
         trigger_window.set_child(Some(&trigger_area));
@@ -40,14 +123,33 @@ impl DockWindow {
         let dock = Rc::new(Self {
             trigger_window: trigger_window.clone(),
             dock_window: Rc::new(RefCell::new(None)),
+            container: RefCell::new(None),
+            items: RefCell::new(Vec::new()),
+            running: RefCell::new(RunningApps::default()),
+            state_rx: compositor().subscribe(),
+            entries_rx: launcher_entry::subscribe(),
+            launches_rx: launch_feedback::subscribe(),
+            apps_rx: app_index::subscribe(),
+            favorites_rx: dock_favorites::subscribe(config),
             app: app.clone(),
+            monitor: monitor.clone(),
             config: config.clone(),
             hide_timer: Rc::new(RefCell::new(None)),
             trigger_hover: Rc::new(RefCell::new(false)),
             dock_hover: Rc::new(RefCell::new(false)),
+            hold_open: Rc::new(RefCell::new(false)),
+            // Intellihide reveals the dock once the first window state shows it unobstructed.
+            obstructed: RefCell::new(config.intellihide),
+            dock_size: RefCell::new(None),
         });
 
-        if config.auto_hide {
+        dock.watch_windows();
+        dock.watch_apps();
+        dock.watch_favorites();
+        dock.watch_launcher_entries();
+        dock.watch_launches();
+
+        if config.auto_hide || config.intellihide {
             dock.setup_auto_hide(&trigger_area);
             trigger_window.present();
         } else {
@@ -57,6 +159,13 @@ impl DockWindow {
         dock
     }
 
+    fn orientation(config: &DockConfig) -> Orientation {
+        match config.position {
+            DockPosition::Bottom => Orientation::Horizontal,
+            DockPosition::Left | DockPosition::Right => Orientation::Vertical,
+        }
+    }
+
     fn setup_trigger_positioning(window: &ApplicationWindow, config: &DockConfig) {
         match config.position {
             crate::utils::config::DockPosition::Bottom => {
@@ -147,222 +256,733 @@ impl DockWindow {
 
         window.add_css_class("Dock");
         window.add_css_class("revealed");
+        window.add_css_class(match self.config.position {
+            DockPosition::Bottom => "bottom",
+            DockPosition::Left => "left",
+            DockPosition::Right => "right",
+        });
         window.init_layer_shell();
+        window.set_monitor(Some(&self.monitor));
         window.set_layer(Layer::Top);
         window.set_namespace(Some("kaneru-dock"));
         window.set_keyboard_mode(KeyboardMode::None);
 
         Self::setup_dock_positioning(&window, &self.config);
 
+        let (wrapper_halign, wrapper_valign) = match self.config.position {
+            DockPosition::Bottom => (gtk4::Align::Center, gtk4::Align::End),
+            DockPosition::Left => (gtk4::Align::Start, gtk4::Align::Center),
+            DockPosition::Right => (gtk4::Align::End, gtk4::Align::Center),
+        };
         let dock_wrapper = GtkBox::new(Orientation::Vertical, 0);
         dock_wrapper.add_css_class("dock-wrapper");
-        dock_wrapper.set_halign(gtk4::Align::Center);
-        dock_wrapper.set_valign(gtk4::Align::End);
+        dock_wrapper.set_halign(wrapper_halign);
+        dock_wrapper.set_valign(wrapper_valign);
 
-        let container = GtkBox::new(Orientation::Horizontal, 4);
+        let container = GtkBox::new(Self::orientation(&self.config), 4);
         container.add_css_class("dock-container");
         container.set_halign(gtk4::Align::Center);
+        container.set_valign(gtk4::Align::Center);
 
         let dock_hover = self.dock_hover.clone();
         let trigger_hover = self.trigger_hover.clone();
 
-        let app_resolver = AppResolver::new();
+        *self.container.borrow_mut() = Some(container.clone());
+        self.populate_items();
+
+        let dock_weak_drop = Rc::downgrade(self);
+        let container_drop = DropTarget::new(String::static_type(), gdk::DragAction::MOVE);
+        container_drop.connect_drop(move |_, value, _, _| {
+            let (Some(dock), Ok(pin_id)) = (dock_weak_drop.upgrade(), value.get::<String>()) else {
+                return false;
+            };
+            dock.drop_favorite(&pin_id, None);
+            true
+        });
+        container.add_controller(container_drop);
 
-        let windows = niri::get_windows().unwrap_or_default();
-        let focused = niri::get_focused_window().ok().flatten();
+        dock_wrapper.append(&container);
+        window.set_child(Some(&dock_wrapper));
 
-        let mut open_desktop_ids = vec![];
-        let mut open_execs = vec![];
-        for w in &windows {
-            if let Some(app_id) = &w.app_id {
-                open_desktop_ids.push(app_id.to_lowercase());
+        let dock_hover_enter = dock_hover.clone();
+        let dock_weak_enter = Rc::downgrade(self);
+        let motion_controller = EventControllerMotion::new();
+        motion_controller.connect_enter(move |_, _, _| {
+            *dock_hover_enter.borrow_mut() = true;
+            if let Some(dock) = dock_weak_enter.upgrade() {
+                if let Some(timer_id) = dock.hide_timer.borrow_mut().take() {
+                    timer_id.remove();
+                }
             }
-            if let Some(title) = &w.title {
-                open_execs.push(title.to_lowercase());
+        });
+
+        let dock_hover_leave = dock_hover.clone();
+        let trigger_hover_leave = trigger_hover.clone();
+        let dock_weak_leave = Rc::downgrade(self);
+        motion_controller.connect_leave(move |_| {
+            *dock_hover_leave.borrow_mut() = false;
+            let trigger_hover_val = *trigger_hover_leave.borrow();
+            if !trigger_hover_val {
+                if let Some(dock) = dock_weak_leave.upgrade() {
+                    dock.schedule_hide_with_hover();
+                }
             }
-        }
-        let focused_app_id = focused.as_ref().and_then(|w| w.app_id.clone()).map(|s| s.to_lowercase());
+        });
 
-        for app_name in &self.config.favorites {
-            if let Some(app_info) = app_resolver.resolve(app_name) {
-                let command = app_info.exec.clone();
+        window.add_controller(motion_controller);
 
-                let icon_name = if app_info.icon.contains("/") || app_info.icon.is_empty() {
-                    "application-x-executable"
-                } else {
-                    &app_info.icon
-                };
+        window.present();
+        *self.dock_window.borrow_mut() = Some(window);
+    }
 
-                let icon = Image::builder()
-                    .icon_name(icon_name)
-                    .pixel_size(40)
-                    .build();
+    fn watch_windows(self: &Rc<Self>) {
+        let mut state_rx = self.state_rx.clone();
+        let dock_weak = Rc::downgrade(self);
 
-                let tooltip = if app_info.name.len() > 50 {
-                    app_name
-                } else {
-                    &app_info.name
+        glib::MainContext::default().spawn_local(async move {
+            loop {
+                let Some(dock) = dock_weak.upgrade() else {
+                    break;
+                };
+                let (running, obstructed) = {
+                    let state = state_rx.borrow_and_update();
+                    (
+                        RunningApps::from_state(&state),
+                        dock.config.intellihide && dock.is_obstructed(&state),
+                    )
                 };
+                if *dock.running.borrow() != running {
+                    dock.sync_items(&running);
+                }
+                if dock.config.intellihide {
+                    dock.set_obstructed(obstructed);
+                }
+                drop(dock);
 
-                let button_box = GtkBox::new(Orientation::Vertical, 0);
-                button_box.set_valign(gtk4::Align::Fill);
-                button_box.set_halign(gtk4::Align::Center);
-                button_box.append(&icon);
+                if state_rx.changed().await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
 
-                let desktop_id = app_info.desktop_id.to_lowercase();
-                let exec_base = app_resolver.extract_command_name(&app_info.exec).to_lowercase();
+    fn is_obstructed(&self, state: &CompositorState) -> bool {
+        let Some(connector) = self.monitor.connector() else {
+            return false;
+        };
+        let Some(workspace) = state
+            .workspaces
+            .values()
+            .find(|ws| ws.is_active && ws.output.as_deref() == Some(connector.as_str()))
+        else {
+            return false;
+        };
 
-                let is_open = open_desktop_ids.iter().any(|id| id == &desktop_id)
-                    || open_execs.iter().any(|ex| ex == &exec_base);
-                let is_active = focused_app_id.as_ref().map(|id| id == &desktop_id || id == &exec_base).unwrap_or(false);
+        let area = self.monitor.geometry();
+        let output_size = (area.width() as f64, area.height() as f64);
+        let dock_rect = self.dock_rect(output_size);
+        state
+            .windows
+            .values()
+            .filter(|w| w.workspace_id == Some(workspace.id))
+            .any(|w| {
+                w.is_fullscreen
+                    || match w.geometry {
+                        Some(WindowGeometry {
+                            position: Some(position),
+                            size,
+                        }) => overlaps(position, size, dock_rect),
+                        // niri does not say where tiled windows are, so rather than guess,
+                        // any of them on the active workspace counts as obstructing.
+                        Some(WindowGeometry { position: None, .. }) => true,
+                        None => false,
+                    }
+            })
+    }
 
-                if is_open || is_active {
-                    let indicator = GtkBox::new(Orientation::Horizontal, 0);
-                    indicator.add_css_class("indicator");
-                    indicator.set_valign(gtk4::Align::End);
-                    indicator.set_halign(gtk4::Align::Center);
-                    button_box.append(&indicator);
-                }
+    fn dock_rect(&self, (output_width, output_height): (f64, f64)) -> (f64, f64, f64, f64) {
+        if let Some(window) = self.dock_window.borrow().as_ref() {
+            if window.width() > 0 && window.height() > 0 {
+                *self.dock_size.borrow_mut() = Some((window.width(), window.height()));
+            }
+        }
 
-                let button = Button::builder()
-                    .child(&button_box)
-                    .tooltip_text(tooltip)
-                    .build();
-                button.set_can_focus(false);
-                button.add_css_class("dock-icon");
+        // Until the dock has been laid out once, assume it spans the whole edge.
+        let thickness = self.config.icon_size as f64 + DOCK_PADDING;
+        let (width, height) = match (*self.dock_size.borrow(), self.config.position) {
+            (Some((width, height)), _) => (width as f64, height as f64),
+            (None, DockPosition::Bottom) => (output_width, thickness),
+            (None, _) => (thickness, output_height),
+        };
+        let x = match self.config.position {
+            DockPosition::Bottom => (output_width - width) / 2.0,
+            DockPosition::Left => 0.0,
+            DockPosition::Right => output_width - width,
+        };
+        let y = match self.config.position {
+            DockPosition::Bottom => output_height - height,
+            DockPosition::Left | DockPosition::Right => (output_height - height) / 2.0,
+        };
+        (x, y, width, height)
+    }
+
+    fn set_obstructed(self: &Rc<Self>, obstructed: bool) {
+        if self.obstructed.replace(obstructed) == obstructed {
+            return;
+        }
+        if !obstructed {
+            self.reveal();
+        } else if !*self.trigger_hover.borrow()
+            && !*self.dock_hover.borrow()
+            && !*self.hold_open.borrow()
+        {
+            self.hide();
+        }
+    }
+
+    fn may_hide(&self) -> bool {
+        if self.config.intellihide {
+            *self.obstructed.borrow()
+        } else {
+            self.config.auto_hide
+        }
+    }
 
-                if is_open {
-                    button.add_css_class("open");
+    fn watch_apps(self: &Rc<Self>) {
+        let mut apps_rx = self.apps_rx.clone();
+        let dock_weak = Rc::downgrade(self);
+
+        glib::MainContext::default().spawn_local(async move {
+            while apps_rx.changed().await.is_ok() {
+                let Some(dock) = dock_weak.upgrade() else {
+                    break;
+                };
+                dock.populate_items();
+            }
+        });
+    }
+
+    fn watch_favorites(self: &Rc<Self>) {
+        let mut favorites_rx = self.favorites_rx.clone();
+        let mut shown = self.favorites();
+        let dock_weak = Rc::downgrade(self);
+
+        glib::MainContext::default().spawn_local(async move {
+            while favorites_rx.changed().await.is_ok() {
+                let Some(dock) = dock_weak.upgrade() else {
+                    break;
+                };
+                let favorites = dock.favorites();
+                if favorites != shown {
+                    shown = favorites;
+                    dock.populate_items();
                 }
-                if is_active {
-                    button.add_css_class("active");
+            }
+        });
+    }
+
+    fn populate_items(self: &Rc<Self>) {
+        let Some(container) = self.container.borrow().clone() else {
+            return;
+        };
+        for item in self.items.borrow_mut().drain(..) {
+            container.remove(&item.button);
+        }
+
+        if self.config.show_favorites {
+            for app_name in self.favorites().iter() {
+                if let Some(item) = self.favorite_item(app_name) {
+                    container.append(&item.button);
+                    self.items.borrow_mut().push(item);
                 }
+            }
+        }
 
-                let parts = crate::utils::app_resolver::AppResolver::clean_exec(&command);
-                button.connect_clicked(move |_| {
-                    if !parts.is_empty() {
-                        let mut cmd = std::process::Command::new(&parts[0]);
-                        if parts.len() > 1 {
-                            cmd.args(&parts[1..]);
-                        }
-                        if let Err(e) = cmd.spawn() {
-                            eprintln!("Failed to launch {:?}: {}", parts, e);
-                        }
-                    }
-                });
-                container.append(&button);
+        let running = RunningApps::from_state(&self.state_rx.borrow());
+        self.sync_items(&running);
+    }
+
+    fn sync_items(self: &Rc<Self>, running: &RunningApps) {
+        *self.running.borrow_mut() = running.clone();
+        let Some(container) = self.container.borrow().clone() else {
+            return;
+        };
+        let mut items = self.items.borrow_mut();
+
+        items.retain(|item| {
+            let keep = item.pinned || running.app_ids.contains(&item.app.app_ids[0]);
+            if !keep {
+                container.remove(&item.button);
+            }
+            keep
+        });
+
+        if self.config.show_running_apps {
+            for app_id in &running.app_ids {
+                if items.iter().any(|item| item.app.app_ids.contains(app_id)) {
+                    continue;
+                }
+                let item = self.running_item(app_id);
+                container.append(&item.button);
+                items.push(item);
             }
         }
 
-        for w in &windows {
-            let app_id = match &w.app_id {
-                Some(id) => id.to_lowercase(),
-                None => continue,
-            };
-            if self.config.favorites.iter().any(|fav| {
-                if let Some(info) = app_resolver.resolve(fav) {
-                    let desktop_id = info.desktop_id.to_lowercase();
-                    let exec_base = app_resolver.extract_command_name(&info.exec).to_lowercase();
-                    app_id == desktop_id || app_id == exec_base
+        for item in items.iter() {
+            let is_open = item.app.app_ids.iter().any(|id| running.app_ids.contains(id));
+            let is_active = running
+                .focused
+                .as_ref()
+                .is_some_and(|id| item.app.app_ids.contains(id));
+            set_css_class(&item.button, "open", is_open);
+            set_css_class(&item.button, "active", is_active);
+            item.indicator.set_visible(is_open || is_active);
+        }
+        drop(items);
+
+        self.update_launcher_entries(&self.entries_rx.borrow());
+        self.update_launches(&self.launches_rx.borrow());
+    }
+
+    fn watch_launcher_entries(self: &Rc<Self>) {
+        let mut entries_rx = self.entries_rx.clone();
+        let dock_weak = Rc::downgrade(self);
+
+        glib::MainContext::default().spawn_local(async move {
+            while entries_rx.changed().await.is_ok() {
+                let entries = entries_rx.borrow_and_update().clone();
+                let Some(dock) = dock_weak.upgrade() else {
+                    break;
+                };
+                dock.update_launcher_entries(&entries);
+            }
+        });
+    }
+
+    fn update_launcher_entries(&self, entries: &LauncherEntries) {
+        for item in self.items.borrow().iter() {
+            let entry = entries.get(&item.app.desktop_id);
+
+            let badge = entry.and_then(|e| e.badge());
+            if let Some(count) = badge {
+                let label = if count > 99 {
+                    "99+".to_string()
                 } else {
-                    false
+                    count.to_string()
+                };
+                item.badge.set_label(&label);
+            }
+            item.badge.set_visible(badge.is_some());
+
+            match entry.and_then(|e| e.progress()) {
+                Some(fraction) => {
+                    item.progress.set_fraction(fraction);
+                    item.progress.set_visible(true);
                 }
-            }) {
-                continue;
-            }
-
-            let (icon_name, command) = if let Some(app_info) = app_resolver.resolve_by_desktop_id(&app_id) {
-                (
-                    if app_info.icon.contains("/") || app_info.icon.is_empty() {
-                        "application-x-executable"
-                    } else {
-                        &app_info.icon
-                    }.to_string(),
-                    app_info.exec.clone(),
-                )
-            } else {
-                ("application-x-executable".to_string(), app_id.clone())
-            };
+                None => item.progress.set_visible(false),
+            }
+
+            set_css_class(&item.button, "urgent", entry.is_some_and(|e| e.urgent));
+        }
+    }
+
+    fn watch_launches(self: &Rc<Self>) {
+        let mut launches_rx = self.launches_rx.clone();
+        let dock_weak = Rc::downgrade(self);
+
+        glib::MainContext::default().spawn_local(async move {
+            while launches_rx.changed().await.is_ok() {
+                let launches = launches_rx.borrow_and_update().clone();
+                let Some(dock) = dock_weak.upgrade() else {
+                    break;
+                };
+                dock.update_launches(&launches);
+            }
+        });
+    }
+
+    fn update_launches(&self, launches: &PendingLaunches) {
+        for item in self.items.borrow().iter() {
+            let launching = launch_feedback::is_launching(launches, &item.app.app_ids);
+            set_css_class(&item.button, "launching", launching);
+        }
+    }
 
-            let icon = Image::builder()
-                .icon_name(&icon_name)
-                .pixel_size(40)
-                .build();
+    fn favorite_item(self: &Rc<Self>, app_name: &str) -> Option<DockItem> {
+        let apps = self.apps_rx.borrow().clone()?;
+        let app_info = apps.resolve(app_name)?;
+        let name = if app_info.name.len() > 50 {
+            app_name
+        } else {
+            &app_info.name
+        };
 
-            let button_box = GtkBox::new(Orientation::Vertical, 0);
-            button_box.set_valign(gtk4::Align::Fill);
-            button_box.set_halign(gtk4::Align::Center);
-            button_box.append(&icon);
+        let app = DockApp {
+            pin_id: app_name.to_string(),
+            desktop_id: app_info.desktop_id.to_lowercase(),
+            name: name.to_string(),
+            app_ids: app_info.app_ids.clone(),
+            info: Some(app_info.clone()),
+        };
+        let icon = icons::app_icon(Some(app_info), app_name);
+        Some(self.build_item(app, true, &icon))
+    }
 
-            let is_active = focused_app_id.as_ref().map(|id| id == &app_id).unwrap_or(false);
+    fn running_item(self: &Rc<Self>, app_id: &str) -> DockItem {
+        let apps = self.apps_rx.borrow().clone();
+        let app_info = apps.as_ref().and_then(|apps| apps.resolve_by_app_id(app_id));
+        let icon = icons::app_icon(app_info, app_id);
+        let app = match app_info {
+            Some(app_info) => {
+                // The running app id stays first so the item is dropped once it closes.
+                let mut app_ids = vec![app_id.to_string()];
+                app_ids.extend(app_info.app_ids.iter().filter(|id| *id != app_id).cloned());
+                DockApp {
+                    pin_id: app_info.desktop_id.clone(),
+                    desktop_id: app_info.desktop_id.to_lowercase(),
+                    name: app_info.name.clone(),
+                    app_ids,
+                    info: Some(app_info.clone()),
+                }
+            }
+            None => DockApp {
+                pin_id: app_id.to_string(),
+                desktop_id: app_id.to_string(),
+                name: app_id.to_string(),
+                app_ids: vec![app_id.to_string()],
+                info: None,
+            },
+        };
+        self.build_item(app, false, &icon)
+    }
 
-            if is_active || true {
-                let indicator = GtkBox::new(Orientation::Horizontal, 0);
-                indicator.add_css_class("indicator");
+    fn build_item(self: &Rc<Self>, app: DockApp, pinned: bool, gicon: &gio::Icon) -> DockItem {
+        let icon_size = self.config.icon_size as i32;
+        let icon = Image::builder()
+            .gicon(gicon)
+            .pixel_size(icon_size)
+            .build();
+
+        let indicator = GtkBox::new(Orientation::Horizontal, 0);
+        indicator.add_css_class("indicator");
+        indicator.set_visible(false);
+
+        // The running indicator sits between the icon and the screen edge.
+        let button_box = match self.config.position {
+            DockPosition::Bottom => {
                 indicator.set_valign(gtk4::Align::End);
                 indicator.set_halign(gtk4::Align::Center);
+                let button_box = GtkBox::new(Orientation::Vertical, 0);
+                button_box.append(&icon);
                 button_box.append(&indicator);
+                button_box
             }
+            DockPosition::Left => {
+                indicator.set_valign(gtk4::Align::Center);
+                indicator.set_halign(gtk4::Align::Start);
+                let button_box = GtkBox::new(Orientation::Horizontal, 0);
+                button_box.append(&indicator);
+                button_box.append(&icon);
+                button_box
+            }
+            DockPosition::Right => {
+                indicator.set_valign(gtk4::Align::Center);
+                indicator.set_halign(gtk4::Align::End);
+                let button_box = GtkBox::new(Orientation::Horizontal, 0);
+                button_box.append(&icon);
+                button_box.append(&indicator);
+                button_box
+            }
+        };
+        button_box.set_valign(gtk4::Align::Fill);
+        button_box.set_halign(gtk4::Align::Center);
 
-            let button = Button::builder()
-                .child(&button_box)
-                .tooltip_text(&app_id)
-                .build();
-            button.set_can_focus(false);
+        let badge = Label::new(None);
+        badge.add_css_class("dock-badge");
+        badge.set_halign(gtk4::Align::End);
+        badge.set_valign(gtk4::Align::Start);
+        badge.set_visible(false);
 
-            let parts = crate::utils::app_resolver::AppResolver::clean_exec(&command);
-            button.connect_clicked(move |_| {
-                if !parts.is_empty() {
-                    let mut cmd = std::process::Command::new(&parts[0]);
-                    if parts.len() > 1 {
-                        cmd.args(&parts[1..]);
-                    }
-                    if let Err(e) = cmd.spawn() {
-                        eprintln!("Failed to launch {:?}: {}", parts, e);
-                    }
-                }
-            });
+        let progress = ProgressBar::new();
+        progress.add_css_class("dock-progress");
+        progress.set_valign(gtk4::Align::End);
+        progress.set_visible(false);
 
-            button.add_css_class("dock-icon");
-            button.add_css_class("open");
-            if is_active {
-                button.add_css_class("active");
-            }
-            container.append(&button);
+        let overlay = Overlay::new();
+        overlay.set_child(Some(&button_box));
+        overlay.add_overlay(&progress);
+        overlay.add_overlay(&badge);
+
+        let button = Button::builder()
+            .child(&overlay)
+            .tooltip_text(&app.name)
+            .build();
+        button.set_can_focus(false);
+        button.add_css_class("dock-icon");
+
+        if self.config.magnification > 1.0 {
+            let magnified = (icon_size as f64 * self.config.magnification).round() as i32;
+            let hover = EventControllerMotion::new();
+            let icon_enter = icon.clone();
+            hover.connect_enter(move |_, _, _| icon_enter.set_pixel_size(magnified));
+            let icon_leave = icon.clone();
+            hover.connect_leave(move |_| icon_leave.set_pixel_size(icon_size));
+            button.add_controller(hover);
         }
 
-        dock_wrapper.append(&container);
-        window.set_child(Some(&dock_wrapper));
+        let app = Rc::new(app);
+        self.connect_app_button(&button, &app);
+        self.connect_drag_and_drop(&button, &icon, &app, pinned);
 
-        let dock_hover_enter = dock_hover.clone();
-        let dock_weak_enter = Rc::downgrade(self);
-        let motion_controller = EventControllerMotion::new();
-        motion_controller.connect_enter(move |_, _, _| {
-            *dock_hover_enter.borrow_mut() = true;
-            if let Some(dock) = dock_weak_enter.upgrade() {
-                if let Some(timer_id) = dock.hide_timer.borrow_mut().take() {
-                    timer_id.remove();
+        DockItem {
+            app,
+            pinned,
+            button,
+            indicator,
+            badge,
+            progress,
+        }
+    }
+
+    fn connect_app_button(self: &Rc<Self>, button: &Button, app: &Rc<DockApp>) {
+        let app_click = app.clone();
+        let dock_click = Rc::downgrade(self);
+        button.connect_clicked(move |button| {
+            let ctrl_held = button
+                .display()
+                .default_seat()
+                .and_then(|seat| seat.keyboard())
+                .is_some_and(|keyboard| {
+                    keyboard
+                        .modifier_state()
+                        .contains(gdk::ModifierType::CONTROL_MASK)
+                });
+            if ctrl_held {
+                launch_app(&app_click, None);
+            } else if let Some(dock) = dock_click.upgrade() {
+                dock.activate_app(&app_click);
+            }
+        });
+
+        let app_middle = app.clone();
+        let middle_click = GestureClick::new();
+        middle_click.set_button(gdk::BUTTON_MIDDLE);
+        middle_click.connect_released(move |gesture, _, _, _| {
+            gesture.set_state(gtk4::EventSequenceState::Claimed);
+            launch_app(&app_middle, None);
+        });
+        button.add_controller(middle_click);
+
+        let app_menu = app.clone();
+        let dock_weak = Rc::downgrade(self);
+        let button_weak = button.downgrade();
+        let secondary_click = GestureClick::new();
+        secondary_click.set_button(gdk::BUTTON_SECONDARY);
+        secondary_click.connect_released(move |gesture, _, _, _| {
+            gesture.set_state(gtk4::EventSequenceState::Claimed);
+            if let (Some(dock), Some(button)) = (dock_weak.upgrade(), button_weak.upgrade()) {
+                dock.show_context_menu(&button, &app_menu);
+            }
+        });
+        button.add_controller(secondary_click);
+    }
+
+    fn connect_drag_and_drop(
+        self: &Rc<Self>,
+        button: &Button,
+        icon: &Image,
+        app: &Rc<DockApp>,
+        pinned: bool,
+    ) {
+        let drag_source = DragSource::new();
+        drag_source.set_actions(gdk::DragAction::MOVE);
+        let pin_id = app.pin_id.clone();
+        drag_source.connect_prepare(move |_, _, _| {
+            Some(gdk::ContentProvider::for_value(&pin_id.to_value()))
+        });
+        let icon = icon.clone();
+        let hold_open = self.hold_open.clone();
+        drag_source.connect_drag_begin(move |source, _| {
+            *hold_open.borrow_mut() = true;
+            source.set_icon(Some(&WidgetPaintable::new(Some(&icon))), 0, 0);
+        });
+        let dock_weak = Rc::downgrade(self);
+        drag_source.connect_drag_end(move |_, _, _| {
+            if let Some(dock) = dock_weak.upgrade() {
+                *dock.hold_open.borrow_mut() = false;
+                let hovered = *dock.dock_hover.borrow() || *dock.trigger_hover.borrow();
+                if dock.may_hide() && !hovered {
+                    dock.schedule_hide_with_hover();
                 }
             }
         });
+        button.add_controller(drag_source);
 
-        let dock_hover_leave = dock_hover.clone();
-        let trigger_hover_leave = trigger_hover.clone();
-        let dock_weak_leave = Rc::downgrade(self);
-        motion_controller.connect_leave(move |_| {
-            *dock_hover_leave.borrow_mut() = false;
-            let trigger_hover_val = *trigger_hover_leave.borrow();
-            if !trigger_hover_val {
-                if let Some(dock) = dock_weak_leave.upgrade() {
+        let drop_target = DropTarget::new(String::static_type(), gdk::DragAction::MOVE);
+        let target_id = app.pin_id.clone();
+        let vertical = self.config.position != DockPosition::Bottom;
+        let dock_weak = Rc::downgrade(self);
+        drop_target.connect_drop(move |target, value, x, y| {
+            let (Some(dock), Ok(pin_id)) = (dock_weak.upgrade(), value.get::<String>()) else {
+                return false;
+            };
+            if !pinned {
+                dock.drop_favorite(&pin_id, None);
+                return true;
+            }
+            let after = match target.widget() {
+                Some(widget) if vertical => y > widget.height() as f64 / 2.0,
+                Some(widget) => x > widget.width() as f64 / 2.0,
+                None => false,
+            };
+            dock.drop_favorite(&pin_id, Some((&target_id, after)));
+            true
+        });
+        button.add_controller(drop_target);
+    }
+
+    fn drop_favorite(&self, pin_id: &str, target: Option<(&str, bool)>) {
+        dock_favorites::move_to(&self.config, pin_id, target);
+    }
+
+    fn show_context_menu(self: &Rc<Self>, button: &Button, app: &Rc<DockApp>) {
+        let popover = Popover::new();
+        popover.add_css_class("dock-menu");
+        popover.set_position(match self.config.position {
+            DockPosition::Bottom => PositionType::Top,
+            DockPosition::Left => PositionType::Right,
+            DockPosition::Right => PositionType::Left,
+        });
+
+        let menu_box = GtkBox::new(Orientation::Vertical, 2);
+        menu_box.add_css_class("dock-menu-box");
+
+        let title = Label::new(Some(&app.name));
+        title.add_css_class("dock-menu-title");
+        title.set_halign(gtk4::Align::Start);
+        menu_box.append(&title);
+
+        for action in app.actions() {
+            let app_action = app.clone();
+            let action_launch = action.clone();
+            let entry = Self::menu_entry(&popover, &action.name, move || {
+                launch_app(&app_action, Some(&action_launch))
+            });
+            menu_box.append(&entry);
+        }
+
+        let app_new = app.clone();
+        menu_box.append(&Self::menu_entry(&popover, "New Window", move || {
+            launch_app(&app_new, None)
+        }));
+
+        menu_box.append(&Separator::new(Orientation::Horizontal));
+
+        let is_pinned = self.favorites().contains(&app.pin_id);
+        let pin_label = if is_pinned {
+            "Unpin from Dock"
+        } else {
+            "Pin to Dock"
+        };
+        let dock_weak = Rc::downgrade(self);
+        let app_pin = app.clone();
+        menu_box.append(&Self::menu_entry(&popover, pin_label, move || {
+            if let Some(dock) = dock_weak.upgrade() {
+                dock.toggle_pin(&app_pin);
+            }
+        }));
+
+        let window_ids = app_windows(self.state_rx.borrow().windows.values(), &app.app_ids);
+        if !window_ids.is_empty() {
+            let entry = Self::menu_entry(&popover, "Close All Windows", move || {
+                for id in &window_ids {
+                    if let Err(e) = compositor().close_window(*id) {
+                        eprintln!("Failed to close window {}: {}", id, e);
+                    }
+                }
+            });
+            entry.add_css_class("destructive");
+            menu_box.append(&entry);
+        }
+
+        popover.set_child(Some(&menu_box));
+        popover.set_parent(button);
+
+        *self.hold_open.borrow_mut() = true;
+        let dock_weak = Rc::downgrade(self);
+        popover.connect_closed(move |popover| {
+            popover.unparent();
+            if let Some(dock) = dock_weak.upgrade() {
+                *dock.hold_open.borrow_mut() = false;
+                let hovered = *dock.dock_hover.borrow() || *dock.trigger_hover.borrow();
+                if dock.may_hide() && !hovered {
                     dock.schedule_hide_with_hover();
                 }
             }
         });
 
-        window.add_controller(motion_controller);
+        popover.popup();
+    }
 
-        window.present();
-        *self.dock_window.borrow_mut() = Some(window);
+    fn menu_entry(popover: &Popover, label: &str, on_activate: impl Fn() + 'static) -> Button {
+        let entry_label = Label::new(Some(label));
+        entry_label.set_halign(gtk4::Align::Start);
+
+        let entry = Button::builder().child(&entry_label).build();
+        entry.add_css_class("dock-menu-item");
+        entry.set_can_focus(false);
+
+        let popover = popover.clone();
+        entry.connect_clicked(move |_| {
+            popover.popdown();
+            on_activate();
+        });
+        entry
+    }
+
+    fn toggle_pin(self: &Rc<Self>, app: &DockApp) {
+        let mut favorites = self.favorites();
+        match favorites.iter().position(|f| *f == app.pin_id) {
+            Some(index) => {
+                favorites.remove(index);
+            }
+            None => favorites.push(app.pin_id.clone()),
+        }
+        self.set_favorites(favorites);
+    }
+
+    /// Focuses the app's next window, or launches it when none are open. Reads
+    /// the windows from the live state so a click never waits on compositor IPC.
+    fn activate_app(&self, app: &DockApp) {
+        let next = {
+            let state = self.state_rx.borrow();
+            let ids = app_windows(state.windows.values(), &app.app_ids);
+            let focused = state
+                .focused_window_id
+                .and_then(|id| ids.iter().position(|w| *w == id));
+            match focused {
+                Some(index) => ids.get((index + 1) % ids.len()).copied(),
+                None => ids.first().copied(),
+            }
+        };
+
+        let Some(next) = next else {
+            launch_app(app, None);
+            return;
+        };
+        if let Err(e) = compositor().focus_window(next) {
+            eprintln!("Failed to focus window {}: {}", next, e);
+        }
+    }
+
+    fn favorites(&self) -> Vec<String> {
+        dock_favorites::get(&self.favorites_rx.borrow(), &self.config)
+    }
+
+    /// Every dock sharing the list, this one included, repopulates from
+    /// `watch_favorites`, after the signal that triggered this has returned.
+    fn set_favorites(&self, favorites: Vec<String>) {
+        dock_favorites::set(&self.config, favorites);
     }
 
     fn reveal(self: &Rc<Self>) {
@@ -380,16 +1000,18 @@ impl DockWindow {
             let dock_weak = Rc::downgrade(self);
             let trigger_hover = self.trigger_hover.clone();
             let dock_hover = self.dock_hover.clone();
+            let hold_open = self.hold_open.clone();
             let hide_delay = self.config.hide_delay;
             let timer_id = glib::timeout_add_local_once(
                 Duration::from_millis(hide_delay as u64),
                 move || {
                     let trigger = *trigger_hover.borrow();
                     let dock = *dock_hover.borrow();
-                    if !trigger && !dock {
-                        if let Some(dock) = dock_weak.upgrade() {
-                            dock.hide();
-                            *dock.hide_timer.borrow_mut() = None;
+                    let held = *hold_open.borrow();
+                    if let Some(dock_window) = dock_weak.upgrade() {
+                        *dock_window.hide_timer.borrow_mut() = None;
+                        if !trigger && !dock && !held && dock_window.may_hide() {
+                            dock_window.hide();
                         }
                     }
                 },
@@ -399,6 +1021,8 @@ impl DockWindow {
     }
 
     fn hide(self: &Rc<Self>) {
+        self.container.borrow_mut().take();
+        self.items.borrow_mut().clear();
         if let Some(window) = self.dock_window.borrow_mut().take() {
             window.close();
         }
@@ -413,4 +1037,67 @@ impl DockWindow {
             self.trigger_window.present();
         }
     }
+
+    pub fn close(&self) {
+        if let Some(timer_id) = self.hide_timer.borrow_mut().take() {
+            timer_id.remove();
+        }
+        if let Some(window) = self.dock_window.borrow_mut().take() {
+            window.close();
+        }
+        self.trigger_window.close();
+    }
+}
+
+fn launch_app(app: &DockApp, action: Option<&DesktopAction>) {
+    let result = match &app.info {
+        Some(info) => launcher::launch(info, action),
+        // Without a desktop entry the best guess is that the app id names the binary.
+        None => launcher::spawn(&[app.desktop_id.clone()]),
+    };
+    match result {
+        Ok(()) => {
+            if let Some(info) = &app.info {
+                app_usage::record_launch(&info.desktop_id);
+            }
+            launch_feedback::track(app.app_ids.clone(), LaunchOrigin::Dock);
+        }
+        Err(e) => eprintln!("Failed to launch {}: {}", app.name, e),
+    }
+}
+
+fn app_windows<'a>(
+    windows: impl IntoIterator<Item = &'a WindowInfo>,
+    app_ids: &[String],
+) -> Vec<u64> {
+    let mut ids: Vec<u64> = windows
+        .into_iter()
+        .filter(|w| {
+            w.app_id
+                .as_ref()
+                .is_some_and(|id| app_ids.contains(&id.to_lowercase()))
+        })
+        .map(|w| w.id)
+        .collect();
+    ids.sort_unstable();
+    ids
+}
+
+fn overlaps(
+    (window_x, window_y): (f64, f64),
+    (window_width, window_height): (f64, f64),
+    (x, y, width, height): (f64, f64, f64, f64),
+) -> bool {
+    window_x < x + width
+        && x < window_x + window_width
+        && window_y < y + height
+        && y < window_y + window_height
+}
+
+fn set_css_class(widget: &impl IsA<gtk4::Widget>, class: &str, enabled: bool) {
+    if enabled {
+        widget.add_css_class(class);
+    } else {
+        widget.remove_css_class(class);
+    }
 }
@@ -6,6 +6,8 @@ mod display_control;
 mod dock;
 mod network;
 mod notification_popup;
+mod power_menu;
+mod window_switcher;
 
 pub use app_menu::AppMenu;
 pub use bar::BarWindow;
@@ -15,3 +17,5 @@ pub use display_control::DisplayControlWindow;
 pub use dock::DockWindow;
 pub use network::NetworkWindow;
 pub use notification_popup::{NotificationPopup, PopupCommand};
+pub use power_menu::PowerMenu;
+pub use window_switcher::WindowSwitcher;
@@ -1,4 +1,4 @@
-use crate::utils::{Notification, NotificationPosition, Urgency};
+use crate::utils::{icons, Notification, NotificationPosition, Urgency};
 use gtk4::prelude::*;
 use gtk4::{
     glib, Align, ApplicationWindow, Box, Button, EventControllerMotion, Image, Justification,
@@ -100,17 +100,11 @@ impl NotificationPopup {
         let header_box = Box::builder().orientation(Orientation::Horizontal).build();
         header_box.add_css_class("header");
 
-        let icon = if notification.app_icon.is_empty() {
-            Image::builder()
-                .icon_name("dialog-information-symbolic")
-                .pixel_size(18)
-                .build()
-        } else {
-            Image::builder()
-                .icon_name(&notification.app_icon)
-                .pixel_size(18)
-                .build()
-        };
+        let gicon = icons::lookup(
+            &[&notification.app_icon, &notification.app_name],
+            "dialog-information-symbolic",
+        );
+        let icon = Image::builder().gicon(&gicon).pixel_size(18).build();
         icon.add_css_class("app-icon");
         header_box.append(&icon);
 
@@ -0,0 +1,216 @@
+use crate::utils::session::{SessionAction, SessionService};
+use gtk4::prelude::*;
+use gtk4::{glib, Align, Box as GtkBox, Button, Image, Label, Orientation, Popover};
+use std::{cell::RefCell, rc::Rc};
+
+/// Seconds a destructive action waits before it runs, during which a
+/// second click cancels it.
+const CONFIRM_SECONDS: u32 = 5;
+
+struct Countdown {
+    action: SessionAction,
+    label: Label,
+    remaining: u32,
+    source: glib::SourceId,
+}
+
+pub struct PowerMenu {
+    popover: Popover,
+    actions_box: GtkBox,
+    service: RefCell<Option<SessionService>>,
+    /// What the menu lists, or `None` before logind has answered.
+    actions: RefCell<Option<Vec<SessionAction>>>,
+    countdown: RefCell<Option<Countdown>>,
+}
+
+impl PowerMenu {
+    pub fn new() -> Rc<Self> {
+        let popover = Popover::new();
+        popover.add_css_class("PowerMenuPopover");
+
+        let actions_box = GtkBox::new(Orientation::Vertical, 4);
+        actions_box.add_css_class("power-menu-container");
+        popover.set_child(Some(&actions_box));
+
+        let menu = Rc::new(Self {
+            popover,
+            actions_box,
+            service: RefCell::new(None),
+            actions: RefCell::new(None),
+            countdown: RefCell::new(None),
+        });
+
+        let menu_weak = Rc::downgrade(&menu);
+        menu.popover.connect_closed(move |_| {
+            if let Some(menu) = menu_weak.upgrade() {
+                menu.cancel_countdown();
+            }
+        });
+
+        // What logind allows can change while kaneru runs, e.g. when hibernation
+        // gets configured, so ask again each time the menu opens.
+        let menu_weak = Rc::downgrade(&menu);
+        menu.popover.connect_show(move |_| {
+            if let Some(menu) = menu_weak.upgrade() {
+                menu.load_actions();
+            }
+        });
+
+        menu.load_actions();
+        menu
+    }
+
+    fn load_actions(self: &Rc<Self>) {
+        let menu_weak = Rc::downgrade(self);
+        glib::MainContext::default().spawn_local(async move {
+            let result = SessionService::shared().await;
+            let actions = match &result {
+                Ok(service) => service.available_actions().await,
+                Err(_) => Vec::new(),
+            };
+            let Some(menu) = menu_weak.upgrade() else {
+                return;
+            };
+            match result {
+                Ok(service) => {
+                    *menu.service.borrow_mut() = Some(service);
+                    menu.show_actions(actions);
+                }
+                Err(e) => {
+                    eprintln!("Failed to connect to logind: {}", e);
+                    if menu.actions_box.first_child().is_none() {
+                        let label = Label::new(Some("Session controls unavailable"));
+                        label.add_css_class("power-menu-unavailable");
+                        menu.actions_box.append(&label);
+                    }
+                }
+            }
+        });
+    }
+
+    fn show_actions(self: &Rc<Self>, actions: Vec<SessionAction>) {
+        if self.actions.borrow().as_ref() == Some(&actions) {
+            return;
+        }
+        // The buttons are about to go, along with any label counting down.
+        self.cancel_countdown();
+        while let Some(child) = self.actions_box.first_child() {
+            self.actions_box.remove(&child);
+        }
+        for &action in &actions {
+            let button = self.create_action_button(action);
+            self.actions_box.append(&button);
+        }
+        *self.actions.borrow_mut() = Some(actions);
+    }
+
+    fn create_action_button(self: &Rc<Self>, action: SessionAction) -> Button {
+        let content = GtkBox::new(Orientation::Horizontal, 10);
+        let icon = Image::from_icon_name(action.icon_name());
+        content.append(&icon);
+        let label = Label::new(Some(action.label()));
+        label.set_halign(Align::Start);
+        label.set_hexpand(true);
+        content.append(&label);
+
+        let button = Button::new();
+        button.add_css_class("power-menu-item");
+        if action.is_destructive() {
+            button.add_css_class("destructive");
+        }
+        button.set_child(Some(&content));
+
+        let menu_weak = Rc::downgrade(self);
+        button.connect_clicked(move |_| {
+            if let Some(menu) = menu_weak.upgrade() {
+                menu.activate(action, &label);
+            }
+        });
+        button
+    }
+
+    fn activate(self: &Rc<Self>, action: SessionAction, label: &Label) {
+        // Clicking the action that is counting down cancels it.
+        if self.cancel_countdown() == Some(action) {
+            return;
+        }
+        if action.is_destructive() {
+            self.start_countdown(action, label);
+        } else {
+            self.popover.popdown();
+            self.perform(action);
+        }
+    }
+
+    fn start_countdown(self: &Rc<Self>, action: SessionAction, label: &Label) {
+        label.set_label(&countdown_text(action, CONFIRM_SECONDS));
+        label.add_css_class("counting-down");
+
+        let menu_weak = Rc::downgrade(self);
+        let source = glib::timeout_add_seconds_local(1, move || {
+            let Some(menu) = menu_weak.upgrade() else {
+                return glib::ControlFlow::Break;
+            };
+            let mut countdown = menu.countdown.borrow_mut();
+            let Some(pending) = countdown.as_mut() else {
+                return glib::ControlFlow::Break;
+            };
+            pending.remaining -= 1;
+            if pending.remaining > 0 {
+                pending
+                    .label
+                    .set_label(&countdown_text(pending.action, pending.remaining));
+                return glib::ControlFlow::Continue;
+            }
+
+            // Taken before popdown so closing the popover finds nothing to cancel.
+            let Some(finished) = countdown.take() else {
+                return glib::ControlFlow::Break;
+            };
+            drop(countdown);
+            reset_label(&finished);
+            menu.popover.popdown();
+            menu.perform(finished.action);
+            glib::ControlFlow::Break
+        });
+
+        *self.countdown.borrow_mut() = Some(Countdown {
+            action,
+            label: label.clone(),
+            remaining: CONFIRM_SECONDS,
+            source,
+        });
+    }
+
+    /// Stops a pending countdown and returns the action it was for.
+    fn cancel_countdown(&self) -> Option<SessionAction> {
+        let countdown = self.countdown.borrow_mut().take()?;
+        reset_label(&countdown);
+        countdown.source.remove();
+        Some(countdown.action)
+    }
+
+    fn perform(&self, action: SessionAction) {
+        let Some(service) = self.service.borrow().clone() else {
+            return;
+        };
+        glib::MainContext::default().spawn_local(async move {
+            if let Err(e) = service.perform(action).await {
+                eprintln!("Failed to {}: {}", action.label().to_lowercase(), e);
+            }
+        });
+    }
+
+    pub fn popover(&self) -> &Popover {
+        &self.popover
+    }
+}
+
+fn countdown_text(action: SessionAction, seconds: u32) -> String {
+    format!("{} in {}s · click to cancel", action.label(), seconds)
+}
+
+fn reset_label(countdown: &Countdown) {
+    countdown.label.set_label(countdown.action.label());
+    countdown.label.remove_css_class("counting-down");
+}
@@ -0,0 +1,357 @@
+use crate::utils::{
+    app_index::{self, AppIndex},
+    compositor,
+    compositor::{CompositorState, WindowInfo},
+    fuzzy::fuzzy_score,
+    icons,
+};
+use gtk4::prelude::*;
+use gtk4::{
+    gdk, glib, Align, Application, ApplicationWindow, Box as GtkBox, EventControllerKey, Image,
+    Label, ListBox, ListBoxRow, Orientation, PolicyType, PropagationPhase, ScrolledWindow,
+    SearchEntry, SelectionMode,
+};
+use gtk4_layer_shell::{KeyboardMode, Layer, LayerShell};
+use pango::EllipsizeMode;
+use std::{cell::RefCell, rc::Rc};
+use tokio::sync::watch;
+
+struct SwitcherEntry {
+    window: WindowInfo,
+    group: String,
+    sort_key: (String, u32, u64),
+}
+
+pub struct WindowSwitcher {
+    window: ApplicationWindow,
+    search_entry: SearchEntry,
+    list_box: ListBox,
+    row_windows: RefCell<Vec<Option<u64>>>,
+    state_rx: watch::Receiver<CompositorState>,
+    apps_rx: watch::Receiver<AppIndex>,
+}
+
+impl WindowSwitcher {
+    pub fn new(app: &Application) -> Rc<Self> {
+        let window = ApplicationWindow::builder().application(app).build();
+        window.add_css_class("WindowSwitcher");
+        window.init_layer_shell();
+        window.set_layer(Layer::Overlay);
+        window.set_namespace(Some("kaneru-window-switcher"));
+        window.set_keyboard_mode(KeyboardMode::Exclusive);
+
+        let container = GtkBox::new(Orientation::Vertical, 6);
+        container.add_css_class("window-switcher-container");
+
+        let search_entry = SearchEntry::new();
+        search_entry.add_css_class("window-switcher-search");
+        search_entry.set_placeholder_text(Some("Search windows"));
+        container.append(&search_entry);
+
+        let scroll = ScrolledWindow::new();
+        scroll.set_min_content_height(420);
+        scroll.set_min_content_width(520);
+        scroll.set_policy(PolicyType::Never, PolicyType::Automatic);
+        scroll.add_css_class("window-switcher-scroll");
+
+        let list_box = ListBox::new();
+        list_box.add_css_class("window-switcher-list");
+        list_box.set_selection_mode(SelectionMode::Single);
+        scroll.set_child(Some(&list_box));
+        container.append(&scroll);
+
+        let hint = Label::new(Some("Enter: focus  ·  Ctrl+W: close  ·  Esc: dismiss"));
+        hint.add_css_class("window-switcher-hint");
+        container.append(&hint);
+
+        window.set_child(Some(&container));
+
+        let switcher = Rc::new(Self {
+            window,
+            search_entry,
+            list_box,
+            row_windows: RefCell::new(Vec::new()),
+            state_rx: compositor().subscribe(),
+            apps_rx: app_index::subscribe(),
+        });
+
+        switcher.connect_signals();
+        switcher
+    }
+
+    fn connect_signals(self: &Rc<Self>) {
+        let switcher_weak = Rc::downgrade(self);
+        self.search_entry.connect_search_changed(move |_| {
+            if let Some(switcher) = switcher_weak.upgrade() {
+                switcher.populate();
+            }
+        });
+
+        let switcher_weak = Rc::downgrade(self);
+        self.search_entry.connect_activate(move |_| {
+            if let Some(switcher) = switcher_weak.upgrade() {
+                switcher.focus_selected();
+            }
+        });
+
+        let switcher_weak = Rc::downgrade(self);
+        self.list_box.connect_row_activated(move |_, row| {
+            if let Some(switcher) = switcher_weak.upgrade() {
+                switcher.list_box.select_row(Some(row));
+                switcher.focus_selected();
+            }
+        });
+
+        let key_controller = EventControllerKey::new();
+        key_controller.set_propagation_phase(PropagationPhase::Capture);
+        let switcher_weak = Rc::downgrade(self);
+        key_controller.connect_key_pressed(move |_, keyval, _, state| {
+            let Some(switcher) = switcher_weak.upgrade() else {
+                return glib::Propagation::Proceed;
+            };
+            let ctrl = state.contains(gdk::ModifierType::CONTROL_MASK);
+            match keyval {
+                gdk::Key::Escape => switcher.hide(),
+                gdk::Key::Return | gdk::Key::KP_Enter => switcher.focus_selected(),
+                gdk::Key::Down => switcher.move_selection(1),
+                gdk::Key::Up => switcher.move_selection(-1),
+                gdk::Key::Tab if !ctrl => switcher.move_selection(1),
+                gdk::Key::ISO_Left_Tab => switcher.move_selection(-1),
+                gdk::Key::w | gdk::Key::W if ctrl => switcher.close_selected(),
+                gdk::Key::Delete if ctrl => switcher.close_selected(),
+                _ => return glib::Propagation::Proceed,
+            }
+            glib::Propagation::Stop
+        });
+        self.window.add_controller(key_controller);
+    }
+
+    pub fn toggle(&self) {
+        if self.window.is_visible() {
+            self.hide();
+        } else {
+            self.show();
+        }
+    }
+
+    pub fn show(&self) {
+        self.search_entry.set_text("");
+        self.populate();
+        self.window.present();
+        self.search_entry.grab_focus();
+    }
+
+    pub fn hide(&self) {
+        self.window.set_visible(false);
+    }
+
+    fn collect_entries(state: &CompositorState) -> Vec<SwitcherEntry> {
+        let mut entries: Vec<SwitcherEntry> = state
+            .windows
+            .values()
+            .map(|window| {
+                let workspace = window.workspace_id.and_then(|id| state.workspaces.get(&id));
+                let output = workspace
+                    .and_then(|ws| ws.output.clone())
+                    .unwrap_or_default();
+                let idx = workspace.map(|ws| ws.idx).unwrap_or(u32::MAX);
+                let workspace_label = match workspace {
+                    Some(ws) => match &ws.name {
+                        Some(name) => format!("{} · {}", ws.idx, name),
+                        None => format!("Workspace {}", ws.idx),
+                    },
+                    None => "No workspace".to_string(),
+                };
+                let group = if output.is_empty() {
+                    workspace_label
+                } else {
+                    format!("{} — {}", output, workspace_label)
+                };
+                SwitcherEntry {
+                    window: window.clone(),
+                    group,
+                    sort_key: (output, idx, window.id),
+                }
+            })
+            .collect();
+        entries.sort_by(|a, b| a.sort_key.cmp(&b.sort_key));
+        entries
+    }
+
+    fn populate(&self) {
+        while let Some(child) = self.list_box.first_child() {
+            self.list_box.remove(&child);
+        }
+        let mut row_windows = self.row_windows.borrow_mut();
+        row_windows.clear();
+
+        let mut entries = Self::collect_entries(&self.state_rx.borrow());
+        let query = self.search_entry.text().to_string();
+
+        if query.trim().is_empty() {
+            let mut current_group: Option<String> = None;
+            for entry in &entries {
+                if current_group.as_deref() != Some(entry.group.as_str()) {
+                    self.list_box
+                        .append(&Self::create_group_header(&entry.group));
+                    row_windows.push(None);
+                    current_group = Some(entry.group.clone());
+                }
+                self.list_box.append(&self.create_window_row(&entry.window));
+                row_windows.push(Some(entry.window.id));
+            }
+        } else {
+            let mut scored: Vec<(i32, SwitcherEntry)> = entries
+                .drain(..)
+                .filter_map(|entry| {
+                    let haystack = format!(
+                        "{} {} {}",
+                        entry.window.title.as_deref().unwrap_or_default(),
+                        entry.window.app_id.as_deref().unwrap_or_default(),
+                        entry.group
+                    );
+                    fuzzy_score(&query, &haystack).map(|score| (score, entry))
+                })
+                .collect();
+            scored.sort_by(|a, b| b.0.cmp(&a.0));
+            for (_, entry) in &scored {
+                self.list_box.append(&self.create_window_row(&entry.window));
+                row_windows.push(Some(entry.window.id));
+            }
+        }
+
+        let first_window_row = row_windows.iter().position(|id| id.is_some());
+        drop(row_windows);
+        match first_window_row {
+            Some(index) => self
+                .list_box
+                .select_row(self.list_box.row_at_index(index as i32).as_ref()),
+            None => self.list_box.select_row(None::<&ListBoxRow>),
+        }
+    }
+
+    fn create_group_header(title: &str) -> ListBoxRow {
+        let label = Label::new(Some(title));
+        label.add_css_class("window-switcher-group");
+        label.set_halign(Align::Start);
+
+        let row = ListBoxRow::new();
+        row.set_child(Some(&label));
+        row.set_selectable(false);
+        row.set_activatable(false);
+        row.set_can_focus(false);
+        row
+    }
+
+    fn icon_for(&self, app_id: &str) -> Image {
+        let resolver = self.apps_rx.borrow();
+        let app = resolver
+            .as_ref()
+            .and_then(|r| r.resolve_by_app_id(app_id).or_else(|| r.resolve(app_id)));
+        let icon = icons::app_icon(app, app_id);
+        Image::builder().gicon(&icon).pixel_size(32).build()
+    }
+
+    fn create_window_row(&self, window: &WindowInfo) -> ListBoxRow {
+        let app_id = window.app_id.clone().unwrap_or_default();
+        let title = window.title.clone().unwrap_or_else(|| app_id.clone());
+
+        let row_box = GtkBox::new(Orientation::Horizontal, 12);
+        row_box.add_css_class("window-switcher-item-box");
+
+        let image = self.icon_for(&app_id);
+        image.add_css_class("window-switcher-item-icon");
+        row_box.append(&image);
+
+        let text_box = GtkBox::new(Orientation::Vertical, 0);
+        text_box.set_hexpand(true);
+
+        let title_label = Label::new(Some(&title));
+        title_label.add_css_class("window-switcher-item-title");
+        title_label.set_halign(Align::Start);
+        title_label.set_ellipsize(EllipsizeMode::End);
+        text_box.append(&title_label);
+
+        let app_label = Label::new(Some(&app_id));
+        app_label.add_css_class("window-switcher-item-app");
+        app_label.set_halign(Align::Start);
+        app_label.set_ellipsize(EllipsizeMode::End);
+        text_box.append(&app_label);
+
+        row_box.append(&text_box);
+
+        let row = ListBoxRow::new();
+        row.add_css_class("window-switcher-item");
+        if window.is_focused {
+            row.add_css_class("focused");
+        }
+        row.set_child(Some(&row_box));
+        row
+    }
+
+    fn selected_window(&self) -> Option<(i32, u64)> {
+        let row = self.list_box.selected_row()?;
+        let index = row.index();
+        let id = self.row_windows.borrow().get(index as usize).copied()??;
+        Some((index, id))
+    }
+
+    fn move_selection(&self, step: i32) {
+        let row_windows = self.row_windows.borrow();
+        if row_windows.is_empty() {
+            return;
+        }
+        let len = row_windows.len() as i32;
+        let mut index = self
+            .list_box
+            .selected_row()
+            .map(|row| row.index())
+            .unwrap_or(if step > 0 { -1 } else { len });
+
+        for _ in 0..len {
+            index = (index + step).rem_euclid(len);
+            if row_windows[index as usize].is_some() {
+                self.list_box
+                    .select_row(self.list_box.row_at_index(index).as_ref());
+                return;
+            }
+        }
+    }
+
+    fn select_nearest(&self, index: i32) {
+        let row_windows = self.row_windows.borrow();
+        let index = index.max(0) as usize;
+        let nearest = (index..row_windows.len())
+            .chain((0..index.min(row_windows.len())).rev())
+            .find(|i| row_windows[*i].is_some());
+        match nearest {
+            Some(i) => self
+                .list_box
+                .select_row(self.list_box.row_at_index(i as i32).as_ref()),
+            None => self.list_box.select_row(None::<&ListBoxRow>),
+        }
+    }
+
+    fn focus_selected(&self) {
+        if let Some((_, id)) = self.selected_window() {
+            if let Err(e) = compositor().focus_window(id) {
+                eprintln!("Failed to focus window {}: {}", id, e);
+            }
+            self.hide();
+        }
+    }
+
+    fn close_selected(&self) {
+        if let Some((index, id)) = self.selected_window() {
+            if let Err(e) = compositor().close_window(id) {
+                eprintln!("Failed to close window {}: {}", id, e);
+                return;
+            }
+            if let Some(row) = self.list_box.row_at_index(index) {
+                self.list_box.remove(&row);
+                self.row_windows.borrow_mut().remove(index as usize);
+            }
+            self.select_nearest(index);
+        }
+    }
+}